@@ -1,18 +1,310 @@
-use crate::clipboard::Clipboard;
-use crate::editor::{Editor, Vector2};
-use crate::renderer::{RenderOpts, Renderer, StringRenderer};
+use crate::clipboard::{self, Clipboard, ClipboardHistory};
+use crate::config::Config;
+use crate::editor::{count_words, Case, Editor, Position, SelectionKind, Vector2};
+use crate::highlight::{self, Highlighter};
+use crate::keymap::{Command, KeyMap};
+use crate::pattern::{self, Pattern};
+use crate::positions::Positions;
+use crate::renderer::{
+    changed_lines, text_box, LineNumberMode, Rect, RenderOpts, Renderer, StringRenderer, Theme,
+};
 
 use crossterm::{
     cursor::MoveTo,
-    input::{InputEvent, KeyEvent, SyncReader},
+    input::{input, InputEvent, KeyEvent},
     screen::{self},
+    style::{ResetColor, SetBackgroundColor, SetForegroundColor},
     terminal::{self},
     ExecutableCommand,
 };
 
-use crossterm::input::{EnableMouseCapture, MouseEvent};
+use crossterm::input::{EnableMouseCapture, MouseButton, MouseEvent};
 use crossterm::terminal::ClearType;
+use std::collections::HashMap;
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant, SystemTime};
+
+/// read the file at `path` into an editor, a file that does not exist yet is an empty editor
+fn read_file(path: &Path) -> std::io::Result<Editor> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(Editor::from(text)),
+        Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Editor::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// the time the file at `path` was last modified, if it can be read
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|x| x.modified()).ok()
+}
+
+/// join `left` and `right` into a status line `width` columns wide with `right` against the
+/// right edge, `left` is cut short where it would run into `right`
+fn fit_status(left: &str, right: &str, width: usize) -> String {
+    let right_width = right.chars().count();
+    if right_width >= width {
+        return left.chars().take(width).collect();
+    }
+
+    let left_width = width - right_width - 1;
+    let left: String = left.chars().take(left_width).collect();
+    format!("{:<2$} {}", left, right, left_width)
+}
+
+/// return the character closing the bracket or quote `c` opens
+fn closing_pair(c: char) -> Option<char> {
+    match c {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '{' => Some('}'),
+        '"' | '\'' => Some(c),
+        _ => None,
+    }
+}
+
+/// the prefix lines are commented out with when the file's extension has none of its own
+pub const DEFAULT_COMMENT_PREFIX: &str = "// ";
+
+/// the comment prefixes of common file types by their extension
+fn default_comment_prefixes() -> HashMap<String, String> {
+    let prefixes: [(&[&str], &str); 4] = [
+        (
+            &["py", "sh", "bash", "rb", "toml", "yaml", "yml", "pl", "r"],
+            "# ",
+        ),
+        (&["lua", "sql", "hs", "elm"], "-- "),
+        (&["vim"], "\" "),
+        (&["tex", "erl"], "% "),
+    ];
+    prefixes
+        .iter()
+        .flat_map(|(extensions, prefix)| {
+            extensions
+                .iter()
+                .map(move |x| (x.to_string(), prefix.to_string()))
+        })
+        .collect()
+}
+
+/// the shape of the terminal cursor
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CursorShape {
+    /// the shape the terminal was configured with
+    Default,
+    /// a steady block over the character, shown while a command is being typed
+    Block,
+    /// a blinking bar between characters, shown while typing text
+    BlinkingBar,
+}
+
+/// changes the shape of the terminal cursor, which crossterm has no command for
+struct SetCursorShape(CursorShape);
+
+impl crossterm::Command for SetCursorShape {
+    type AnsiType = &'static str;
+
+    fn ansi_code(&self) -> Self::AnsiType {
+        match self.0 {
+            CursorShape::Default => "\x1b[0 q",
+            CursorShape::Block => "\x1b[2 q",
+            CursorShape::BlinkingBar => "\x1b[5 q",
+        }
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> crossterm::Result<()> {
+        Ok(())
+    }
+}
+
+/// the register used when no register has been named
+pub const UNNAMED_REGISTER: char = '"';
+
+/// text stored in a register
+#[derive(Clone, Debug, PartialEq)]
+pub struct Register {
+    pub text: String,
+    /// the text is made of whole lines, and is pasted below the cursor's line
+    pub linewise: bool,
+}
+
+/// an edit which can be repeated with M-.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// a run of characters typed one after the other
+    Insert(String),
+    /// a run of characters deleted one after the other
+    Delete(usize),
+    /// the character under the cursor overwritten
+    Replace(char),
+}
+
+/// what is done with the text typed into the prompt
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Action {
+    /// replace text across the buffer, typed as `find/replace/` with an optional `g` flag
+    Replace,
+    /// run an ex style command such as `w`, `q` or a line number
+    ExCommand,
+    /// open the file at the typed path
+    Open,
+    /// move to the next occurrence of text, or of a regular expression if `regex` is set.
+    /// searches towards the start of the buffer if `reverse` is set
+    Search { regex: bool, reverse: bool },
+}
+
+impl Action {
+    /// the text shown before the prompt
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::Replace => ":s/",
+            Action::ExCommand => ":",
+            Action::Open => "open: ",
+            Action::Search {
+                regex: false,
+                reverse: false,
+            } => "/",
+            Action::Search {
+                regex: true,
+                reverse: false,
+            } => "regex /",
+            Action::Search {
+                regex: false,
+                reverse: true,
+            } => "?",
+            Action::Search {
+                regex: true,
+                reverse: true,
+            } => "regex ?",
+        }
+    }
+}
+
+/// the number of lines the view moves for each tick of the mouse wheel
+pub const MOUSE_SCROLL_LINES: i32 = 3;
+
+/// how long a message stays in the status line
+const LOG_TIMEOUT: Duration = Duration::from_secs(3);
+/// how long an error message stays in the status line
+const ERROR_LOG_TIMEOUT: Duration = Duration::from_secs(10);
+/// how often timed behaviors are checked while waiting for input
+const TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// the keybindings reference shown over the buffer by F1
+const HELP_TEXT: &str = include_str!("../resources/help_text.txt");
+
+/// the number of entries kept in the history of each prompt
+pub const PROMPT_HISTORY_LENGTH: usize = 50;
+
+/// the number of locations kept in the jump list
+pub const JUMP_LIST_LENGTH: usize = 100;
+
+/// a line of text being typed into the status line
+#[derive(Clone, Debug, PartialEq)]
+pub struct Prompt {
+    pub action: Action,
+    pub text: String,
+    // the history entry being shown, and the text typed before browsing the history
+    history_index: Option<usize>,
+    draft: String,
+}
+
+impl Prompt {
+    pub fn new(action: Action) -> Self {
+        Self {
+            action,
+            text: String::new(),
+            history_index: None,
+            draft: String::new(),
+        }
+    }
+}
+
+/// a replace typed into the prompt
+#[derive(Clone, Debug, PartialEq)]
+pub struct Substitute {
+    pub find: String,
+    pub replace: String,
+    /// replace every occurrence on a line rather than the first
+    pub global: bool,
+    /// ask before replacing each occurrence
+    pub confirm: bool,
+}
+
+/// parse the text of a replace prompt, typed as `find/replace/` followed by
+/// the flags `g` and `c`. a slash is escaped as `\/`
+pub fn parse_substitute(text: &str) -> Option<Substitute> {
+    let mut parts = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('/') => parts.last_mut().unwrap().push('/'),
+                Some(x) => {
+                    parts.last_mut().unwrap().push('\\');
+                    parts.last_mut().unwrap().push(x);
+                }
+                None => parts.last_mut().unwrap().push('\\'),
+            },
+            '/' => parts.push(String::new()),
+            x => parts.last_mut().unwrap().push(x),
+        }
+    }
+
+    let flags = match parts.len() {
+        2 => "",
+        3 => parts[2].as_str(),
+        _ => return None,
+    };
+    if !flags.chars().all(|x| x == 'g' || x == 'c') {
+        return None;
+    }
+    Some(Substitute {
+        find: parts[0].clone(),
+        replace: parts[1].clone(),
+        global: flags.contains('g'),
+        confirm: flags.contains('c'),
+    })
+}
+
+/// a replace waiting for confirmation of each occurrence
+#[derive(Clone, Debug, PartialEq)]
+struct ConfirmReplace {
+    substitute: Substitute,
+    // the occurrence being confirmed, and where to search from after it
+    current: Option<Vector2>,
+    next: Vector2,
+    count: usize,
+}
+
+/// a file open in the application, stored while another buffer is being edited
+struct Buffer {
+    editor: Editor,
+    file_path: Option<PathBuf>,
+    path_chosen: bool,
+    last_known_mtime: Option<SystemTime>,
+    // the location of the view over the buffer
+    view: Vector2,
+    jump_list: Vec<Vector2>,
+    jump_index: usize,
+}
+
+impl Buffer {
+    fn new() -> Buffer {
+        Buffer {
+            editor: Editor::new(),
+            file_path: None,
+            path_chosen: false,
+            last_known_mtime: None,
+            view: Vector2(0, 0),
+            jump_list: Vec::new(),
+            jump_index: 0,
+        }
+    }
+}
 
 /// handles the main application logic
 pub struct Application<T>
@@ -20,29 +312,258 @@ where
     T: Clipboard,
 {
     pub editor: Editor,
-    pub clipboard: T,
+    pub clipboard: ClipboardHistory<T>,
     pub render_opts: RenderOpts,
+    pub highlighter: Option<Rc<dyn Highlighter>>,
     pub exit: bool,
     pub log: String,
+    // whether the message in the log is an error, which stays longer
+    log_is_error: bool,
+    // the message the log timer was started for and when it is cleared
+    log_expiry: Option<(String, Instant)>,
+    /// the file the buffer is written to
+    pub file_path: Option<PathBuf>,
+    // whether the user opened or saved to the file path, only then is it auto-saved
+    path_chosen: bool,
+    /// the modification time of the file when it was last read or written
+    pub last_known_mtime: Option<SystemTime>,
+    // every open buffer, the entry for the buffer being edited is empty
+    // as its state lives in the fields above
+    buffers: Vec<Buffer>,
+    current_buffer: usize,
+    /// copy the file to a backup before overwriting it on save
+    pub make_backup: bool,
+    /// appended to the file name to name the backup
+    pub backup_suffix: String,
+    /// the state file the cursor location in each file is remembered in between runs
+    pub positions_file: Option<PathBuf>,
+    /// the command each key runs
+    pub keymap: KeyMap,
+    /// save the buffer once it has gone this long without an edit
+    pub auto_save: Option<Duration>,
+    /// keys and commands which change the buffer do nothing
+    pub read_only: bool,
+    /// sorting lines compares letters regardless of case
+    pub sort_ignore_case: bool,
+    // the edit count of the buffer and when it was last seen to change
+    last_edit: Option<(u64, Instant)>,
+    // the first line of the help text shown while it is drawn over the buffer
+    help_scroll: Option<usize>,
+
+    // number typed before a motion, the motion is repeated this many times
+    pub pending_count: Option<u32>,
+
+    // where the mouse was pressed while it is held down
+    mouse_anchor: Option<Vector2>,
+
+    // whether the status line last drawn marked the buffer as modified
+    shown_modified: bool,
+    // the shape the terminal cursor was last set to
+    shown_cursor_shape: CursorShape,
+    // waiting for y or n after asking to quit with unsaved changes
+    awaiting_quit: bool,
+    // waiting for y or n after asking to overwrite a file changed on disk,
+    // holds the file and whether to quit after writing it
+    awaiting_overwrite: Option<(PathBuf, bool)>,
+
+    // waiting for the target character of a find, holds (forward, till)
+    awaiting_find: Option<(bool, bool)>,
+    // the locations the cursor jumped from, oldest first, and the entry ^P and ^N are at.
+    // the index is the length of the list when no entry has been returned to
+    jump_list: Vec<Vector2>,
+    jump_index: usize,
+    // the last find, repeated with M-; and M-,
+    last_find: Option<Position>,
+
+    // waiting for the character to overwrite the cursor's character with
+    awaiting_replace: bool,
+    // waiting for z, t or b to place the cursor's line in the view
+    awaiting_view_align: bool,
+    // the register keys are being recorded into and the keys recorded so far
+    recording: Option<(char, Vec<KeyEvent>)>,
+    // keys recorded into each register, replayed with M-@
+    macros: HashMap<char, Vec<KeyEvent>>,
+    last_macro: Option<char>,
+    // waiting for the register to record into
+    awaiting_record: bool,
+    // waiting for the register to replay, holds the number of times to replay it
+    awaiting_replay: Option<u32>,
+    // keys are being replayed, the screen is drawn once they have all been processed
+    replaying: bool,
+    // waiting for the name of a mark, true to set it and false to jump to it
+    awaiting_mark: Option<bool>,
+    // waiting for the delimiter to surround the selection with
+    awaiting_surround: bool,
+    // the keys typed after M-o, an operator, i or a and the kind of text object,
+    // or the operator twice to act on whole lines, and the count typed before M-o
+    awaiting_text_object: Option<(String, u32)>,
+
+    // the last edit, and whether the next edit continues it
+    last_change: Option<Change>,
+    change_in_progress: bool,
+
+    // text copied into named registers
+    pub registers: HashMap<char, Register>,
+    // waiting for the name of a register, and the register named for the next command
+    awaiting_register: bool,
+    pending_register: Option<char>,
+    // the start and end of the text inserted by the last paste from the clipboard
+    last_paste: Option<(Vector2, Vector2)>,
+
+    // text being typed into the status line
+    pub prompt: Option<Prompt>,
+    // the cursor and view location when the prompt was opened, restored when a search is cancelled
+    prompt_origin: (Vector2, Vector2),
+    // a replace asking whether to replace each occurrence
+    confirm_replace: Option<ConfirmReplace>,
+    // the last text searched for, and whether it was a regular expression
+    last_search: Option<(String, bool)>,
+    // text previously entered into each kind of prompt, oldest first
+    search_history: Vec<String>,
+    replace_history: Vec<String>,
+    command_history: Vec<String>,
+    file_history: Vec<String>,
+    /// searches match letters regardless of case
+    pub search_ignore_case: bool,
+    /// searches containing an uppercase letter match case even when ignoring case
+    pub search_smart_case: bool,
+    /// searches continue from the other end of the buffer after reaching the end
+    pub search_wrap: bool,
+    /// the number of lines kept between the cursor and the top or bottom of the view
+    pub scroll_off: i32,
+    /// the number of columns kept between the cursor and the left or right of the view
+    pub side_scroll_off: i32,
+    /// the width paragraphs are reflowed to
+    pub text_width: usize,
+    /// typing an opening bracket or quote also types its closing one after the cursor
+    pub auto_close: bool,
+    /// Tab types a tab character instead of spaces to the next tab stop
+    pub hard_tabs: bool,
+    /// the prefix lines are commented out with, by the extension of the file being edited
+    pub comment_prefixes: HashMap<String, String>,
+    // paint the matches of the last search until escape is pressed
+    search_highlight: bool,
+    // the number of the match at the cursor and the total number of matches of the last search
+    search_position: Option<(usize, usize)>,
 
     // hint to only render a particular line
     render_line_hint: Option<i32>,
     render_break_line_hint: bool,
+    // the lines drawn by the last render, lines which have not changed are not drawn again
+    last_frame: Vec<String>,
 }
 
 impl<T> Application<T>
 where
     T: Clipboard,
 {
+    /// Create an application editing the file at `path`, which is written to on save.
+    /// a file that does not exist yet starts as an empty buffer
+    pub fn open(path: impl Into<PathBuf>, clipboard: T) -> std::io::Result<Application<T>> {
+        let path = path.into();
+        let editor = read_file(&path)?;
+
+        let mut app = Application::new(editor, clipboard);
+        app.last_known_mtime = modified_time(&path);
+        app.file_path = Some(path);
+        app.path_chosen = true;
+        app.update_highlighter();
+        Ok(app)
+    }
+
     pub fn new(editor: Editor, clipboard: T) -> Application<T> {
+        let config = Config::default();
         Application {
             editor,
-            clipboard,
-            render_opts: RenderOpts::default(),
+            clipboard: ClipboardHistory::new(clipboard),
+            render_opts: RenderOpts {
+                tab_width: config.tab_width,
+                theme: config.theme,
+                line_numbers: config.line_numbers,
+                ..RenderOpts::default()
+            },
+            highlighter: None,
             exit: false,
+            log_is_error: false,
+            log_expiry: None,
             log: String::new(),
+            file_path: None,
+            path_chosen: false,
+            last_known_mtime: None,
+            buffers: vec![Buffer::new()],
+            current_buffer: 0,
+            make_backup: false,
+            backup_suffix: "~".to_string(),
+            positions_file: None,
+            keymap: KeyMap::default(),
+            auto_save: config.auto_save,
+            read_only: false,
+            sort_ignore_case: config.sort_ignore_case,
+            last_edit: None,
+            help_scroll: None,
+            pending_count: None,
+            mouse_anchor: None,
+            shown_modified: false,
+            shown_cursor_shape: CursorShape::Default,
+            awaiting_quit: false,
+            awaiting_overwrite: None,
+            awaiting_find: None,
+            jump_list: Vec::new(),
+            jump_index: 0,
+            last_find: None,
+            awaiting_replace: false,
+            awaiting_view_align: false,
+            recording: None,
+            macros: HashMap::new(),
+            last_macro: None,
+            awaiting_record: false,
+            awaiting_replay: None,
+            replaying: false,
+            awaiting_mark: None,
+            awaiting_surround: false,
+            awaiting_text_object: None,
+            last_change: None,
+            change_in_progress: false,
+            registers: HashMap::new(),
+            awaiting_register: false,
+            pending_register: None,
+            last_paste: None,
+            prompt: None,
+            prompt_origin: (Vector2(0, 0), Vector2(0, 0)),
+            confirm_replace: None,
+            last_search: None,
+            search_history: Vec::new(),
+            replace_history: Vec::new(),
+            command_history: Vec::new(),
+            file_history: Vec::new(),
+            search_ignore_case: config.search_ignore_case,
+            search_smart_case: true,
+            search_wrap: true,
+            scroll_off: config.scroll_off,
+            side_scroll_off: 5,
+            text_width: 80,
+            auto_close: true,
+            hard_tabs: false,
+            comment_prefixes: default_comment_prefixes(),
+            search_highlight: false,
+            search_position: None,
             render_line_hint: None,
             render_break_line_hint: false,
+            last_frame: Vec::new(),
+        }
+    }
+
+    /// change the settings to those read from a config file
+    pub fn apply_config(&mut self, config: &Config) {
+        self.render_opts.tab_width = config.tab_width;
+        self.render_opts.theme = config.theme;
+        self.render_opts.line_numbers = config.line_numbers;
+        self.search_ignore_case = config.search_ignore_case;
+        self.sort_ignore_case = config.sort_ignore_case;
+        self.scroll_off = config.scroll_off;
+        self.auto_save = config.auto_save;
+        for &(key, command) in &config.keys {
+            self.keymap.bind(key, command);
         }
     }
 
@@ -51,33 +572,183 @@ where
         // enter raw mode
         // switch to the alternate screen
         let _alternate = screen::AlternateScreen::to_alternate(true)?;
-        // process keyboard events
-        let mut reader = SyncReader {};
+        // input is read on its own thread so the loop can wake up between events
+        let (sender, events) = mpsc::channel();
+        std::thread::spawn(move || {
+            for event in input().read_sync() {
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        });
 
         // enable mouse capture
         std::io::stdout().execute(EnableMouseCapture).unwrap();
 
         self.render();
+        self.update_cursor_shape();
 
-        loop {
-            if self.exit {
-                break Ok(());
+        let mut next_tick = Instant::now() + TICK_INTERVAL;
+        while !self.exit {
+            let timeout = next_tick.saturating_duration_since(Instant::now());
+            match events.recv_timeout(timeout) {
+                Ok(event) => self.process_event(event),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
             }
 
-            if let Some(event) = reader.next() {
-                self.process_event(event);
+            let now = Instant::now();
+            if now >= next_tick {
+                self.on_tick(now);
+                next_tick = now + TICK_INTERVAL;
             }
-
-            // thread::sleep(std::time::Duration::from_millis(50));
         }
+
+        self.save_positions();
+        // give the terminal its own cursor back
+        std::io::stdout().execute(SetCursorShape(CursorShape::Default))?;
+        Ok(())
     }
 
     pub fn process_event(&mut self, event: InputEvent) {
+        self.log_is_error = false;
         match event {
             InputEvent::Keyboard(event) => self.process_key_event(event),
             InputEvent::Mouse(event) => self.process_mouse_event(event),
             _ => {}
         }
+        self.start_log_timer(Instant::now());
+        self.update_cursor_shape();
+    }
+
+    /// run the behaviors which happen over time rather than in response to input,
+    /// called by the main loop about every `TICK_INTERVAL` at `now`
+    pub fn on_tick(&mut self, now: Instant) {
+        // resizing the terminal sends no input, so its size is checked here
+        let resized = self.update_view_size().unwrap_or(false);
+        let saved = self.auto_save_file(now);
+        if self.expire_log(now) || saved || resized {
+            self.render();
+        }
+    }
+
+    /// write the buffer to its file once `auto_save` has passed since its last edit at `now`.
+    /// a file is only saved automatically once the user has opened or saved to it
+    ///
+    /// # Returns
+    /// true if the buffer was saved or saving it failed
+    pub fn auto_save_file(&mut self, now: Instant) -> bool {
+        let interval = match self.auto_save {
+            Some(interval) => interval,
+            None => return false,
+        };
+        let edits = self.editor.edit_count();
+        let edited = match self.last_edit {
+            Some((count, time)) if count == edits => time,
+            _ => {
+                self.last_edit = Some((edits, now));
+                now
+            }
+        };
+        let path = match self.file_path {
+            Some(ref path) if self.path_chosen => path.clone(),
+            _ => return false,
+        };
+        if !self.editor.is_modified() || now < edited + interval {
+            return false;
+        }
+
+        // a failed save is tried again after another interval
+        self.last_edit = Some((edits, now));
+        match self.save_to_file(&path) {
+            Ok(_) => {
+                self.log = "auto-saved".to_string();
+                self.log_is_error = false;
+            }
+            Err(e) => self.error(format!("auto-save failed: {}", e)),
+        }
+        self.start_log_timer(now);
+        true
+    }
+
+    /// show an error in the status line, it is kept longer than other messages
+    fn error(&mut self, message: String) {
+        self.log = message;
+        self.log_is_error = true;
+    }
+
+    /// start timing how long a new message has been shown from `now`
+    fn start_log_timer(&mut self, now: Instant) {
+        let is_new = match self.log_expiry {
+            Some((ref shown, _)) => *shown != self.log || self.log_is_error,
+            None => true,
+        };
+        if self.log.is_empty() {
+            self.log_expiry = None;
+        } else if is_new {
+            let timeout = if self.log_is_error {
+                ERROR_LOG_TIMEOUT
+            } else {
+                LOG_TIMEOUT
+            };
+            self.log_expiry = Some((self.log.clone(), now + timeout));
+        }
+    }
+
+    /// clear the message from the status line once it has been shown long enough at `now`,
+    /// questions waiting for an answer are kept until they are answered
+    ///
+    /// # Returns
+    /// true if the message was cleared
+    pub fn expire_log(&mut self, now: Instant) -> bool {
+        let waiting = self.awaiting_quit
+            || self.awaiting_overwrite.is_some()
+            || self.awaiting_text_object.is_some()
+            || self.confirm_replace.is_some();
+        match self.log_expiry {
+            Some((ref shown, expiry)) if now >= expiry && !waiting => {
+                if *shown == self.log {
+                    self.log.clear();
+                }
+                self.log_expiry = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// the cursor shape for what the next key does, a block while a count, a key after a prefix,
+    /// a selection or an answer to a question is pending and a bar while text is being typed
+    pub fn cursor_shape(&self) -> CursorShape {
+        let awaiting_key = self.pending_count.is_some()
+            || self.help_scroll.is_some()
+            || self.awaiting_quit
+            || self.awaiting_overwrite.is_some()
+            || self.awaiting_find.is_some()
+            || self.awaiting_replace
+            || self.awaiting_view_align
+            || self.awaiting_mark.is_some()
+            || self.awaiting_record
+            || self.awaiting_replay.is_some()
+            || self.awaiting_surround
+            || self.awaiting_text_object.is_some()
+            || self.awaiting_register
+            || self.confirm_replace.is_some();
+        let typing = self.prompt.is_some() || !self.read_only;
+        if awaiting_key || !typing || (self.editor.is_selecting() && self.prompt.is_none()) {
+            CursorShape::Block
+        } else {
+            CursorShape::BlinkingBar
+        }
+    }
+
+    /// set the terminal cursor to the shape for the current state when it has changed
+    fn update_cursor_shape(&mut self) {
+        let shape = self.cursor_shape();
+        if shape != self.shown_cursor_shape {
+            std::io::stdout().execute(SetCursorShape(shape)).unwrap();
+            self.shown_cursor_shape = shape;
+        }
     }
 
     pub fn process_mouse_event(&mut self, event: MouseEvent) {
@@ -85,29 +756,363 @@ where
 
         self.log = "Processing mouse event".to_string();
 
-        // convert screen coordinates into editor coordinates
-        macro_rules! to_editor_coords {
-            ($x:ident, $y:ident) => {{
-                let Vector2(x2, y2) = self.render_opts.view.location;
-                ($x + x2, $y + y2)
-            }};
-        }
-
         match event {
+            Press(MouseButton::WheelUp, _, _) => {
+                self.scroll_view(-MOUSE_SCROLL_LINES);
+                self.render();
+            }
+            Press(MouseButton::WheelDown, _, _) => {
+                self.scroll_view(MOUSE_SCROLL_LINES);
+                self.render();
+            }
             Press(_, x, y) => {
-                let (x, y) = (x as i32, y as i32);
-                let (x, y) = to_editor_coords!(x, y);
-                self.log = format!("mouse: set cursor location to {}:{}", x, y);
-                self.editor.set_cursor((x, y));
+                let location = self.screen_to_buffer(x, y);
+                self.log = format!(
+                    "mouse: set cursor location to {}:{}",
+                    location.x(),
+                    location.y()
+                );
+                self.mouse_press(location);
                 self.render();
             }
+            Hold(x, y) => {
+                let location = self.screen_to_buffer(x, y);
+                self.mouse_drag(location);
+                self.render();
+            }
+            Release(_, _) => self.mouse_anchor = None,
             _ => self.log = "unknown mouse event".to_string(),
         }
     }
 
+    /// move the view and the cursor down half a screen, or up if `down` is not set.
+    /// the view stays between the first and last lines of the buffer
+    pub fn scroll_half_page(&mut self, down: bool) {
+        let half = (self.render_opts.view.height / 2).max(1);
+        let amount = if down { half } else { -half };
+        let last = self.editor.line_count() as i32 - 1;
+
+        let view = &mut self.render_opts.view;
+        view.location.1 = (view.location.1 + amount).min(last).max(0);
+        self.editor.move_cursor((0, amount));
+    }
+
+    /// convert screen coordinates into editor coordinates
+    fn screen_to_buffer(&self, x: u16, y: u16) -> Vector2 {
+        let Vector2(x2, y2) = self.render_opts.view.location;
+        let gutter = self.render_opts.gutter_width(&self.editor);
+        if self.render_opts.wrap {
+            let position = Vector2((x as i32 - gutter).max(0), y as i32);
+            return self.render_opts.wrapped_location(&self.editor, position);
+        }
+        let y = y as i32 + y2;
+        let x = self
+            .render_opts
+            .buffer_column(&self.editor, y, x as i32 - gutter + x2);
+        Vector2(x, y)
+    }
+
+    /// move the cursor to a clicked location, which anchors a selection if the mouse is dragged
+    pub fn mouse_press(&mut self, location: Vector2) {
+        self.editor.clear_selection();
+        self.editor.set_cursor(location);
+        self.mouse_anchor = Some(self.editor.cursor_pos());
+    }
+
+    /// select from where the mouse was pressed to `location`
+    pub fn mouse_drag(&mut self, location: Vector2) {
+        if let Some(anchor) = self.mouse_anchor {
+            if !self.editor.is_selecting() {
+                self.editor.begin_select_at(anchor);
+            }
+            self.editor.set_cursor(location);
+        }
+    }
+
+    /// move the view down by `lines`, or up when negative, without moving the cursor.
+    /// the view stops a screen above the first line
+    pub fn scroll_view(&mut self, lines: i32) {
+        let view = &mut self.render_opts.view;
+        view.location.1 = (view.location.1 + lines).max(-view.height);
+    }
+
     pub fn process_key_event(&mut self, event: KeyEvent) {
         use KeyEvent::*;
 
+        // keys are recorded until recording is stopped with M-Q, the keys a replay
+        // processes are not as the key that started the replay already was
+        let stops_recording = self.keymap.get(event) == Some(Command::Record);
+        if let Some((_, ref mut keys)) = self.recording {
+            if !stops_recording && !self.replaying {
+                keys.push(event);
+            }
+        }
+
+        // any key other than another edit ends the current change
+        let continue_change = std::mem::replace(&mut self.change_in_progress, false);
+
+        // keys scroll the help while it is shown, any other key closes it
+        if let Some(scroll) = self.help_scroll {
+            let page = (self.render_opts.view.height - 2).max(1) as usize;
+            let last = HELP_TEXT.lines().count().saturating_sub(page);
+            self.help_scroll = match event {
+                Up => Some(scroll.saturating_sub(1)),
+                Down => Some((scroll + 1).min(last)),
+                PageUp => Some(scroll.saturating_sub(page)),
+                PageDown => Some((scroll + page).min(last)),
+                _ => None,
+            };
+            self.render();
+            return;
+        }
+
+        // keys are typed into the prompt while it is open
+        if self.prompt.is_some() {
+            self.process_prompt_key(event);
+            return;
+        }
+
+        // keys answer whether to replace the current occurrence
+        if self.confirm_replace.is_some() {
+            match event {
+                Char(x) => self.answer_replace(x),
+                Esc => self.finish_replace(),
+                _ => {}
+            }
+            self.render();
+            return;
+        }
+
+        // quitting with unsaved changes waits for y, any other key cancels
+        if std::mem::replace(&mut self.awaiting_quit, false) {
+            if event == Char('y') {
+                self.exit = true;
+            } else {
+                self.log.clear();
+                self.render();
+            }
+            return;
+        }
+
+        // overwriting a file changed on disk waits for y, any other key cancels
+        if let Some((path, quit)) = self.awaiting_overwrite.take() {
+            if event == Char('y') {
+                self.last_known_mtime = None;
+                self.exit = self.write_file(Some(&path)) && quit;
+            } else {
+                self.log.clear();
+            }
+            if !self.exit {
+                self.render();
+            }
+            return;
+        }
+
+        // the key following a find is the character to search for
+        if let Some((forward, till)) = self.awaiting_find.take() {
+            if let Char(target) = event {
+                let find = Position::FindChar {
+                    target,
+                    forward,
+                    till,
+                };
+                self.last_find = Some(find);
+                let previous = self.editor.cursor_pos();
+                self.editor.move_cursor_to(find);
+                self.cursor_moved(previous);
+                return;
+            }
+        }
+
+        // the key following a replace is written over the cursor's character
+        if std::mem::replace(&mut self.awaiting_replace, false) {
+            if let Char(x) = event {
+                let change = Change::Replace(x);
+                self.replay_change(&change);
+                self.last_change = Some(change);
+                self.render_line_hint = Some(self.editor.cursor_pos().y());
+                self.render();
+                return;
+            }
+        }
+
+        // the key following M-z places the cursor's line in the view
+        if std::mem::replace(&mut self.awaiting_view_align, false) {
+            if let Char(x) = event {
+                self.align_view(x);
+                self.render();
+                return;
+            }
+        }
+
+        // the key following M-Q names the register to record into
+        if std::mem::replace(&mut self.awaiting_record, false) {
+            if let Char(name) = event {
+                self.start_recording(name);
+                self.render();
+                return;
+            }
+        }
+
+        // the key following M-@ names the register to replay, @ replays the last one again
+        if let Some(count) = self.awaiting_replay.take() {
+            if let Char(name) = event {
+                match if name == '@' {
+                    self.last_macro
+                } else {
+                    Some(name)
+                } {
+                    Some(name) => {
+                        self.replay_macro(name, count);
+                    }
+                    None => self.log = "no macro has been replayed".to_string(),
+                }
+                self.render();
+                return;
+            }
+        }
+
+        // the key following M-m or M-` names the mark to set or jump to
+        if let Some(set) = self.awaiting_mark.take() {
+            if let Char(name) = event {
+                if set {
+                    self.set_mark(name);
+                    self.render();
+                } else {
+                    let previous = self.editor.cursor_pos();
+                    if self.jump_to_mark(name) {
+                        self.cursor_jumped(previous);
+                    } else {
+                        self.render();
+                    }
+                }
+                return;
+            }
+        }
+
+        // the key following M-a is the delimiter to surround the selection with
+        if std::mem::replace(&mut self.awaiting_surround, false) {
+            if let Char(x) = event {
+                self.surround_selection(x);
+                self.render();
+                return;
+            }
+        }
+
+        // the keys following M-o name an operator and the text object it acts on
+        if let Some((mut keys, count)) = self.awaiting_text_object.take() {
+            if let Char(x) = event {
+                keys.push(x);
+                if keys == "dd" || keys == "yy" {
+                    let register = self.pending_register.take();
+                    self.apply_line_operator(x, count, register);
+                    self.scroll_to_cursor();
+                } else if keys.chars().count() < 3 {
+                    self.log = format!("text object: {}", keys);
+                    self.awaiting_text_object = Some((keys, count));
+                } else {
+                    let register = self.pending_register.take();
+                    self.log.clear();
+                    self.apply_text_object(&keys, register);
+                }
+                self.render();
+                return;
+            }
+        }
+
+        // the key following a register prefix names the register
+        if std::mem::replace(&mut self.awaiting_register, false) {
+            if let Char(name) = event {
+                self.pending_register = Some(name);
+                self.log = format!("register {}", name);
+                self.render();
+                return;
+            }
+        }
+
+        // a digit following a paste replaces it with an older copy from the clipboard history
+        if let Some((start, end)) = self.last_paste.take() {
+            if let Char(x @ '0'..='9') = event {
+                let n = x.to_digit(10).unwrap() as usize;
+                match self.clipboard.paste_nth(n) {
+                    Some(text) => {
+                        self.editor.cut_range(start, end);
+                        self.editor.set_cursor(start);
+                        for x in text.chars().filter(|&x| x != '\r') {
+                            self.editor.write(x);
+                        }
+                        self.last_paste = Some((start, self.editor.cursor_pos()));
+                    }
+                    None => {
+                        self.log = format!("no copy {} in the clipboard history", n);
+                        self.last_paste = Some((start, end));
+                    }
+                }
+                self.render();
+                return;
+            }
+        }
+
+        // accumulate digits into a count, a zero is only part of a count once one has begun
+        if let Alt(x @ '0'..='9') = event {
+            if x != '0' || self.pending_count.is_some() {
+                let digit = x.to_digit(10).unwrap();
+                self.pending_count = Some(
+                    self.pending_count
+                        .unwrap_or(0)
+                        .saturating_mul(10)
+                        .saturating_add(digit),
+                );
+                self.render();
+                return;
+            }
+        }
+
+        let count = self.pending_count.take();
+        let register = self.pending_register.take();
+
+        // keys that are not bound to a command are typed
+        match self.keymap.get(event) {
+            Some(command) if self.read_only && command.is_edit() => self.refuse_edit(),
+            Some(command) => self.run_command(command, count, register, continue_change),
+            None if self.read_only && matches!(event, Char(_)) => self.refuse_edit(),
+            None => {
+                if let Char(x) = event {
+                    self.type_char(x);
+                    self.record_insert(x, continue_change);
+                    if !self.scroll_to_cursor() {
+                        self.render_break_line_hint = true;
+                        self.render_line_hint = Some(self.editor.cursor_pos().y());
+                    }
+                    self.render();
+                }
+            }
+        }
+
+        // clear the count from the status bar
+        if count.is_some() {
+            self.render();
+        }
+    }
+
+    /// leave a read-only buffer unchanged and say why
+    fn refuse_edit(&mut self) {
+        self.log = "buffer is read-only".to_string();
+        self.render();
+    }
+
+    /// run the command bound to a key, repeated by or given `count`
+    /// and copying to or pasting from `register`.
+    /// `continue_change` is set when the previous key was also an edit
+    fn run_command(
+        &mut self,
+        command: Command,
+        count: Option<u32>,
+        register: Option<char>,
+        continue_change: bool,
+    ) {
+        use Command::*;
+
         macro_rules! move_view {
             ($x:expr, $y:expr) => {
                 self.render_opts.view.location =
@@ -118,8 +1123,17 @@ where
 
         macro_rules! move_cursor {
             ($x:expr, $y:expr) => {
+                let previous = self.editor.cursor_pos();
                 self.editor.move_cursor(($x, $y));
-                self.update_cursor_pos();
+                self.cursor_moved(previous);
+            };
+        }
+
+        macro_rules! move_cursor_to {
+            ($position:expr) => {
+                let previous = self.editor.cursor_pos();
+                self.editor.move_cursor_to($position);
+                self.cursor_moved(previous);
             };
         }
 
@@ -134,132 +1148,1980 @@ where
             };
         }
 
-        match event {
-            Down => {
-                move_cursor!(0, 1);
+        let repeat = count.unwrap_or(1) as i32;
+
+        match command {
+            MoveDown => {
+                move_cursor!(0, repeat);
             }
-            Up => {
-                move_cursor!(0, -1);
+            MoveUp => {
+                move_cursor!(0, -repeat);
             }
-            Right => {
-                move_cursor!(1, 0);
+            MoveRight => {
+                move_cursor!(repeat, 0);
             }
-            Left => {
-                move_cursor!(-1, 0);
+            MoveLeft => {
+                move_cursor!(-repeat, 0);
             }
-            CtrlDown => {
+            ScrollDown => {
                 move_view!(0, 1);
             }
-            CtrlUp => {
+            ScrollUp => {
                 move_view!(0, -1);
             }
-            CtrlRight => {
+            ScrollRight => {
                 move_view!(1, 0);
             }
-            CtrlLeft => {
+            ScrollLeft => {
                 move_view!(-1, 0);
             }
-            F(1) => {
-                use crossterm::terminal::Clear;
-                std::io::stdout().execute(MoveTo(0, 0)).unwrap();
-                std::io::stdout().execute(Clear(ClearType::All)).unwrap();
-                println!("{}", include_str!("../resources/help_text.txt"));
+            Help => {
+                self.help_scroll = Some(0);
+                self.render();
             }
-            F(5) => {
+            Refresh => {
+                self.last_frame.clear();
                 self.render();
             }
-            Ctrl('c') => {
-                self.exit = true;
+            Quit => {
+                self.quit(false);
+                if !self.exit {
+                    self.render();
+                }
+            }
+            Save => {
+                self.write_file(None);
+                self.render();
             }
-            Ctrl('a') => {
+            CursorToView => {
                 // bring the cursor to the top of the viewport
                 set_cursor!((
                     0,
                     self.render_opts.view.location.y() + (self.render_opts.view.height / 2)
                 ));
             }
-            Ctrl('l') => {
-                // center the screen on the cursor
-                self.render_opts.view.location.1 =
-                    self.editor.cursor_pos().y() - (self.render_opts.view.height / 2);
+            CenterView => {
+                self.center_renderer();
                 self.render();
             }
-            Char(x) => {
-                self.editor.write(x);
-                self.render_break_line_hint = true;
-                self.render_line_hint = Some(self.editor.cursor_pos().y());
+            Stats => {
+                self.log = self.stats();
                 self.render();
             }
-            Backspace => {
-                if let Some(x) = self.editor.delete() {
-                    if x.char != '\n' {
-                        self.render_line_hint = Some(self.editor.cursor_pos().y());
-                    }
-                }
+            FirstLine => {
+                // jump to the first line
+                self.push_jump(self.editor.cursor_pos());
+                self.editor.move_cursor_to(Position::Line(0));
+                self.center_renderer();
                 self.render();
             }
-            Enter => {
-                self.editor.write('\n');
+            GoToLine => {
+                // jump to the line given by the count, or the last line without one.
+                // out of range lines are clamped by the editor
+                let line = count.map_or(i32::MAX, |x| {
+                    (x.min(i32::MAX as u32) as i32).saturating_sub(1)
+                });
+                self.push_jump(self.editor.cursor_pos());
+                self.editor.move_cursor_to(Position::Line(line));
+                self.center_renderer();
                 self.render();
             }
-            Home => {
-                set_cursor!(0, self.editor.cursor_pos().y());
-            }
-            End => {
-                set_cursor!(self.editor.line_len() as i32, self.editor.cursor_pos().y());
+            JumpBack | JumpForward => {
+                let jumped = if command == JumpBack {
+                    self.jump_back()
+                } else {
+                    self.jump_forward()
+                };
+                if jumped && !self.cursor_in_view() {
+                    self.center_renderer();
+                }
+                self.render();
+            }
+            Backspace => {
+                let mut deleted = 0;
+                // a selection may span several lines
+                let mut deleted_line = self.editor.is_selecting();
+                'repeat: for _ in 0..repeat {
+                    // remove a whole level of indentation at once
+                    let width = if self.editor.is_selecting() {
+                        1
+                    } else {
+                        self.editor.backspace_width(self.render_opts.tab_width)
+                    };
+                    for _ in 0..width {
+                        match self.editor.delete() {
+                            Some(x) if x.char == '\n' => deleted_line = true,
+                            Some(_) => {}
+                            None => break 'repeat,
+                        }
+                        deleted += 1;
+                    }
+                }
+                if deleted > 0 {
+                    self.record_delete(deleted, continue_change);
+                }
+                if !self.scroll_to_cursor() && !deleted_line {
+                    self.render_line_hint = Some(self.editor.cursor_pos().y());
+                }
+                self.render();
+            }
+            RepeatChange => {
+                // replaying a change does not replace it as the last change
+                if let Some(change) = self.last_change.clone() {
+                    for _ in 0..repeat {
+                        self.replay_change(&change);
+                    }
+                    self.render();
+                }
+            }
+            Select => {
+                self.editor.begin_select();
+                self.log = "selecting characters".to_string();
+                self.render();
+            }
+            SelectLines => {
+                self.editor.begin_line_select();
+                self.log = "selecting lines".to_string();
+                self.render();
+            }
+            SelectBlock => {
+                self.editor.begin_block_select();
+                self.log = "selecting a block".to_string();
+                self.render();
+            }
+            Escape => {
+                self.editor.clear_selection();
+                self.search_highlight = false;
+                self.search_position = None;
+                self.log = String::new();
+                self.render();
+            }
+            Register => self.awaiting_register = true,
+            Copy => {
+                self.copy_selection(register);
+                self.render();
+            }
+            Paste => {
+                let inserted = self.paste(register, repeat.max(1) as usize);
+                if register.is_none() && repeat == 1 {
+                    self.last_paste = inserted;
+                }
+                self.render();
+            }
+            LineNumbers => {
+                self.render_opts.line_numbers = self.render_opts.line_numbers.next();
+                self.log = format!("line numbers: {:?}", self.render_opts.line_numbers);
+                self.render();
+            }
+            HighlightLine => {
+                self.render_opts.highlight_current_line = !self.render_opts.highlight_current_line;
+                self.render();
+            }
+            ShowWhitespace => {
+                self.render_opts.render_whitespace = !self.render_opts.render_whitespace;
+                self.render();
+            }
+            Wrap => {
+                self.toggle_wrap();
+                self.render();
+            }
+            ExpandTabs => {
+                let lines = self.editor.expand_tabs(self.render_opts.tab_width);
+                self.log = format!("expanded tabs on {} lines", lines);
+                self.render();
+            }
+            UnexpandTabs => {
+                let lines = self.editor.unexpand_tabs(self.render_opts.tab_width);
+                self.log = format!("converted indentation to tabs on {} lines", lines);
+                self.render();
+            }
+            YankLines => {
+                if self.editor.is_selecting() {
+                    self.copy_selection(register);
+                } else {
+                    self.apply_line_operator('y', repeat as u32, register);
+                }
+                self.render();
+            }
+            DeleteChar | DeleteCharBefore if self.editor.is_selecting() => {
+                self.cut_selection(register);
+                self.scroll_to_cursor();
+                self.render();
+            }
+            DeleteChar | DeleteCharBefore => {
+                let deleted = self
+                    .editor
+                    .delete_chars(repeat as usize, command == DeleteChar);
+                if !deleted.is_empty() {
+                    let text = deleted.iter().map(|x| x.char).collect();
+                    if let Err(e) = self.store_register(register, text, false) {
+                        self.error(e.to_string());
+                    }
+                }
+                self.render_line_hint = Some(self.editor.cursor_pos().y());
+                self.render();
+            }
+            DeleteToEnd | ChangeToEnd => {
+                let text: String = self
+                    .editor
+                    .delete_to(Position::LineEnd)
+                    .iter()
+                    .map(|x| x.char)
+                    .collect();
+                if let Err(e) = self.store_register(register, text, false) {
+                    self.error(e.to_string());
+                }
+                // D rests on the last remaining character, C stays where typing continues
+                if command == DeleteToEnd {
+                    self.editor.move_cursor((-1, 0));
+                }
+                self.render_line_hint = Some(self.editor.cursor_pos().y());
+                self.render();
+            }
+            JoinLines => {
+                for _ in 0..repeat {
+                    if !self.editor.join_line() {
+                        break;
+                    }
+                }
+                self.render();
+            }
+            AlignView => self.awaiting_view_align = true,
+            DuplicateLines => {
+                for _ in 0..repeat {
+                    self.editor.duplicate_line();
+                }
+                self.render();
+            }
+            HalfPageDown => {
+                self.scroll_half_page(true);
+                self.render();
+            }
+            HalfPageUp => {
+                self.scroll_half_page(false);
+                self.render();
+            }
+            MoveLinesDown | MoveLinesUp => {
+                let lines = match self.editor.selection_range() {
+                    Some((start, end)) => start.y()..=end.y(),
+                    None => {
+                        let y = self.editor.cursor_pos().y();
+                        y..=y
+                    }
+                };
+                let delta = if command == MoveLinesDown {
+                    repeat
+                } else {
+                    -repeat
+                };
+                if self.editor.move_lines(lines, delta) {
+                    self.render();
+                }
+            }
+            Indent | Dedent => {
+                // the selection is kept so the lines can be shifted again,
+                // without one the count is the number of lines to shift
+                let (lines, times) = match self.editor.selection_range() {
+                    Some((start, end)) => (start.y()..=end.y(), repeat),
+                    None => {
+                        let y = self.editor.cursor_pos().y();
+                        (y..=y + repeat - 1, 1)
+                    }
+                };
+                for _ in 0..times {
+                    if command == Indent {
+                        self.editor
+                            .indent_lines(lines.clone(), self.render_opts.tab_width);
+                    } else {
+                        self.editor
+                            .dedent_lines(lines.clone(), self.render_opts.tab_width);
+                    }
+                }
+                self.render();
+            }
+            SortLines | SortLinesReverse => match self.editor.selection_range() {
+                Some((start, end)) => {
+                    self.editor.clear_selection();
+                    self.editor.sort_lines(
+                        start.y()..=end.y(),
+                        command == SortLinesReverse,
+                        self.sort_ignore_case,
+                    );
+                    self.log = "sorted lines".to_string();
+                    self.render();
+                }
+                None => self.log = "nothing selected".to_string(),
+            },
+            Reflow => {
+                let lines = match self.editor.selection_range() {
+                    Some((start, end)) => start.y()..=end.y(),
+                    None => self.editor.paragraph_lines(),
+                };
+                let count = self.editor.reflow(lines, self.text_width);
+                self.log = format!("reflowed to {} lines", count);
+                self.scroll_to_cursor();
+                self.render();
+            }
+            NextParagraph | PreviousParagraph => {
+                let previous = self.editor.cursor_pos();
+                let paragraph = if command == NextParagraph {
+                    Position::NextParagraph
+                } else {
+                    Position::PreviousParagraph
+                };
+                for _ in 0..repeat {
+                    self.editor.move_cursor_to(paragraph);
+                }
+                self.cursor_jumped(previous);
+            }
+            MatchingBracket => {
+                let previous = self.editor.cursor_pos();
+                self.editor.move_cursor_to(Position::MatchingBracket);
+                self.cursor_jumped(previous);
+            }
+            FirstNonBlank => {
+                move_cursor_to!(Position::FirstNonBlank);
+            }
+            LineEnd => {
+                move_cursor_to!(Position::LineEnd);
+            }
+            Increment | Decrement => {
+                let delta = if command == Increment {
+                    repeat
+                } else {
+                    -repeat
+                };
+                if self.editor.adjust_number(delta as i64) {
+                    self.render_line_hint = Some(self.editor.cursor_pos().y());
+                    self.render();
+                }
+            }
+            ToggleCase | Lowercase | Uppercase => {
+                let case = match command {
+                    Lowercase => Case::Lower,
+                    Uppercase => Case::Upper,
+                    _ => Case::Toggle,
+                };
+                if self.editor.is_selecting() {
+                    self.editor.change_case_selection(case);
+                } else {
+                    for _ in 0..repeat {
+                        if !self.editor.change_case_at_cursor(case) {
+                            break;
+                        }
+                    }
+                }
+                self.render();
+            }
+            Search | SearchBackward => {
+                self.open_prompt(Action::Search {
+                    regex: false,
+                    reverse: command == SearchBackward,
+                });
+                self.render();
+            }
+            OpenFile => {
+                self.open_prompt(Action::Open);
+                self.render();
+            }
+            ExCommand => {
+                self.open_prompt(Action::ExCommand);
+                self.render();
+            }
+            Replace => {
+                self.open_prompt(Action::Replace);
+                self.render();
+            }
+            ToggleIgnoreCase => {
+                self.search_ignore_case = !self.search_ignore_case;
+                self.log = format!("search ignores case: {}", self.search_ignore_case);
+                self.render();
+            }
+            ReplaceChar => self.awaiting_replace = true,
+            SetMark => self.awaiting_mark = Some(true),
+            Record => {
+                if self.recording.is_some() {
+                    self.stop_recording();
+                    self.render();
+                } else {
+                    self.awaiting_record = true;
+                }
+            }
+            Replay => self.awaiting_replay = Some(count.unwrap_or(1)),
+            JumpToMark => self.awaiting_mark = Some(false),
+            Surround => match self.editor.selection_kind() {
+                _ if !self.editor.is_selecting() => self.log = "nothing selected".to_string(),
+                SelectionKind::Block => {
+                    self.log = "block selections cannot be surrounded".to_string()
+                }
+                _ => self.awaiting_surround = true,
+            },
+            Comment => {
+                let lines = match self.editor.selection_range() {
+                    Some((start, end)) => start.y()..=end.y(),
+                    None => {
+                        let y = self.editor.cursor_pos().y();
+                        y..=y + repeat - 1
+                    }
+                };
+                let prefix = self.comment_prefix();
+                self.log = if self.editor.toggle_comment(lines, &prefix) {
+                    "commented lines".to_string()
+                } else {
+                    "uncommented lines".to_string()
+                };
+                self.render();
+            }
+            TextObject => {
+                self.awaiting_text_object = Some((String::new(), repeat as u32));
+                self.log = "text object: ".to_string();
+                self.render();
+            }
+            FindChar => self.awaiting_find = Some((true, false)),
+            FindCharBackward => self.awaiting_find = Some((false, false)),
+            TillChar => self.awaiting_find = Some((true, true)),
+            TillCharBackward => self.awaiting_find = Some((false, true)),
+            RepeatFind | RepeatFindReverse => {
+                if let Some(Position::FindChar {
+                    target,
+                    forward,
+                    till,
+                }) = self.last_find
+                {
+                    // a comma repeats the find in the opposite direction
+                    move_cursor_to!(Position::FindChar {
+                        target,
+                        forward: forward == (command == RepeatFind),
+                        till,
+                    });
+                }
+            }
+            LineStart => {
+                set_cursor!(0, self.editor.cursor_pos().y());
+            }
+            NewLine => {
+                // the new line keeps the indentation before the cursor
+                let x = self.editor.cursor_pos().x() as usize;
+                let indent: String = self.editor.current_indent().chars().take(x).collect();
+                self.editor.write('\n');
+                self.record_insert('\n', continue_change);
+                for c in indent.chars() {
+                    self.editor.write(c);
+                    self.record_insert(c, true);
+                }
+                self.scroll_to_cursor();
+                self.render();
+            }
+            Tab if self.editor.is_selecting() => {
+                // a selection is indented like M->
+                if let Some((start, end)) = self.editor.selection_range() {
+                    self.editor
+                        .indent_lines(start.y()..=end.y(), self.render_opts.tab_width);
+                }
+                self.render();
+            }
+            Tab => {
+                let mut continue_change = continue_change;
+                for _ in 0..repeat {
+                    for c in self.tab_text().chars() {
+                        self.editor.write(c);
+                        self.record_insert(c, continue_change);
+                        continue_change = true;
+                    }
+                }
+                self.scroll_to_cursor();
+                self.render();
+            }
+            Outdent => {
+                let lines = match self.editor.selection_range() {
+                    Some((start, end)) => start.y()..=end.y(),
+                    None => {
+                        let y = self.editor.cursor_pos().y();
+                        y..=y
+                    }
+                };
+                for _ in 0..repeat {
+                    self.editor
+                        .dedent_lines(lines.clone(), self.render_opts.tab_width);
+                }
+                self.render();
+            }
+            Home => {
+                // move to the indentation first, then to the start of the line
+                let cursor = self.editor.cursor_pos();
+                if self.editor.position_of(Position::FirstNonBlank) == cursor {
+                    set_cursor!(0, cursor.y());
+                } else {
+                    move_cursor_to!(Position::FirstNonBlank);
+                }
+            }
+            End => {
+                set_cursor!(self.editor.line_len() as i32, self.editor.cursor_pos().y());
+            }
+        }
+    }
+
+    /// render the screen to crossterm.
+    /// if self.render_line_hint is not None, only that line will be rendered
+    pub fn render(&mut self) {
+        if self.replaying {
+            return;
+        }
+        // a resized terminal is drawn again in full
+        if self.update_view_size().unwrap() {
+            self.clear_render_hints();
+        }
+
+        // render a single line if the line hint is not None,
+        // unless the modified marker in the status line needs redrawing
+        // or a wrapped line could change how many rows it takes
+        if let Some(line) = self.render_line_hint {
+            if self.editor.is_modified() == self.shown_modified && !self.render_opts.wrap {
+                self.render_line(line);
+                return;
+            }
+            self.clear_render_hints();
+        }
+        self.shown_modified = self.editor.is_modified();
+
+        let text = StringRenderer {
+            highlighter: self.highlighter.clone(),
+            search_matches: self.search_matches(),
+            ..StringRenderer::new()
+        }
+        .render(&self.editor, self.render_opts);
+
+        let count = self
+            .pending_count
+            .map_or(String::new(), |x| format!("{} ", x));
+        let matches = self
+            .search_position
+            .map_or(String::new(), |(current, total)| {
+                format!("[{}/{}] ", current, total)
+            });
+        let case = if self.search_ignore_case {
+            "[ignore case] "
+        } else {
+            ""
+        };
+
+        let theme = self.render_opts.theme;
+        let status_color = format!(
+            "{}{}",
+            SetForegroundColor(theme.status_fg),
+            SetBackgroundColor(theme.status_bg)
+        );
+
+        // only the lines which changed since the last render are drawn
+        let mut stdout = std::io::stdout();
+        for (y, line) in changed_lines(&self.last_frame, &text) {
+            stdout.execute(MoveTo(0, y as u16)).unwrap();
+            write!(&mut stdout, "{}", line).unwrap();
+        }
+        self.last_frame = text.lines().map(String::from).collect();
+
+        // the help is drawn over the buffer, and the rows under it are redrawn once it closes
+        if let Some(scroll) = self.help_scroll {
+            let view = Rect {
+                location: Vector2(0, 0),
+                ..self.render_opts.view
+            };
+            let (area, rows) = text_box(HELP_TEXT, scroll, view);
+            for (i, row) in rows.iter().enumerate() {
+                let y = area.y() + i as i32;
+                stdout.execute(MoveTo(area.x() as u16, y as u16)).unwrap();
+                write!(&mut stdout, "{}{}{}", status_color, row, ResetColor).unwrap();
+                if let Some(drawn) = self.last_frame.get_mut(y as usize) {
+                    drawn.clear();
+                }
+            }
+        }
+
+        let status_line = self.render_opts.view.height as u16;
+        stdout.execute(MoveTo(0, status_line)).unwrap();
+        match self.prompt {
+            Some(ref prompt) => {
+                write!(
+                    &mut stdout,
+                    "{}{}{}",
+                    status_color,
+                    prompt.action.label(),
+                    prompt.text
+                )
+                .unwrap();
+                stdout
+                    .execute(terminal::Clear(ClearType::UntilNewLine))
+                    .unwrap();
+                write!(&mut stdout, "{}", ResetColor).unwrap();
+
+                // the cursor is placed at the end of the prompt
+                let x = prompt.action.label().chars().count() + prompt.text.chars().count();
+                let y = self.render_opts.view.height;
+                stdout.execute(MoveTo(x as u16, y as u16)).unwrap();
+                return;
+            }
+            None => {
+                let left = format!(
+                    "{} [F1 to display help ] {}{}{}{}",
+                    self.file_status(),
+                    count,
+                    matches,
+                    case,
+                    self.log
+                );
+                let width = self.render_opts.view.width.max(0) as usize;
+                write!(
+                    &mut stdout,
+                    "{}{}{}",
+                    status_color,
+                    fit_status(&left, &self.cursor_status(), width),
+                    ResetColor
+                )
+                .unwrap()
+            }
+        }
+
+        self.update_cursor_pos();
+    }
+
+    /// edit the prompt, running its action when enter is pressed
+    fn process_prompt_key(&mut self, event: KeyEvent) {
+        use KeyEvent::*;
+
+        let prompt = match self.prompt {
+            Some(ref mut prompt) => prompt,
+            None => return,
+        };
+        match event {
+            Char(x) => prompt.text.push(x),
+            // deleting past the start of the prompt closes it
+            Backspace if prompt.text.pop().is_none() => self.cancel_prompt(),
+            Esc => self.cancel_prompt(),
+            Ctrl('r') => {
+                // switch between searching for text and for a regular expression
+                if let Action::Search { regex, reverse } = prompt.action {
+                    prompt.action = Action::Search {
+                        regex: !regex,
+                        reverse,
+                    };
+                }
+            }
+            Up => self.recall_history(true),
+            Down => self.recall_history(false),
+            Enter => {
+                let prompt = self.prompt.take().unwrap();
+                let history = self.prompt_history(prompt.action);
+                if !prompt.text.is_empty() && history.last() != Some(&prompt.text) {
+                    history.push(prompt.text.clone());
+                    if history.len() > PROMPT_HISTORY_LENGTH {
+                        history.remove(0);
+                    }
+                }
+                // searches start from where the cursor was before the preview
+                self.restore_prompt_origin();
+                self.run_action(prompt.action, &prompt.text);
+                self.render();
+                return;
             }
             _ => {}
         }
+        self.preview_search();
+        self.render();
+    }
+
+    /// open the prompt for `action`, remembering the cursor and view location
+    pub fn open_prompt(&mut self, action: Action) {
+        self.prompt = Some(Prompt::new(action));
+        self.prompt_origin = (self.editor.cursor_pos(), self.render_opts.view.location);
+    }
+
+    /// close the prompt, returning the cursor to where it was before a search preview
+    pub fn cancel_prompt(&mut self) {
+        if let Some(Prompt {
+            action: Action::Search { .. },
+            ..
+        }) = self.prompt.take()
+        {
+            self.restore_prompt_origin();
+        }
+    }
+
+    fn restore_prompt_origin(&mut self) {
+        let (cursor, view) = self.prompt_origin;
+        self.editor.set_cursor(cursor);
+        self.render_opts.view.location = view;
+    }
+
+    /// move the cursor to the first match of the text typed into a search prompt,
+    /// or back to where it started if nothing matches
+    pub fn preview_search(&mut self) {
+        let (text, regex, reverse) = match self.prompt {
+            Some(Prompt {
+                action: Action::Search { regex, reverse },
+                ref text,
+                ..
+            }) => (text.clone(), regex, reverse),
+            _ => return,
+        };
+        self.restore_prompt_origin();
+
+        let pattern = match self.search_pattern(&text, regex) {
+            Ok(pattern) if !text.is_empty() => pattern,
+            _ => return,
+        };
+        let cursor = self.editor.cursor_pos();
+        let from = if reverse { cursor } else { cursor.add((1, 0)) };
+        if let Some((location, _)) =
+            self.editor
+                .search_wrapping(&pattern, from, reverse, self.search_wrap)
+        {
+            self.editor.set_cursor(location);
+            if !self.cursor_in_view() {
+                self.center_renderer();
+            }
+        }
+    }
+
+    /// the text previously entered into prompts for `action`
+    fn prompt_history(&mut self, action: Action) -> &mut Vec<String> {
+        match action {
+            Action::Search { .. } => &mut self.search_history,
+            Action::Replace => &mut self.replace_history,
+            Action::ExCommand => &mut self.command_history,
+            Action::Open => &mut self.file_history,
+        }
+    }
+
+    /// replace the text of the prompt with an older entry from its history,
+    /// or a newer one if `older` is not set. moving past the newest entry restores the typed text
+    pub fn recall_history(&mut self, older: bool) {
+        let mut prompt = match self.prompt.take() {
+            Some(prompt) => prompt,
+            None => return,
+        };
+        let history = self.prompt_history(prompt.action);
+
+        let index = match (prompt.history_index, older) {
+            (None, true) if !history.is_empty() => Some(history.len() - 1),
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (Some(i), false) if i + 1 < history.len() => Some(i + 1),
+            _ => None,
+        };
+        match index {
+            Some(i) => {
+                if prompt.history_index.is_none() {
+                    prompt.draft = prompt.text.clone();
+                }
+                prompt.text = history[i].clone();
+            }
+            None if prompt.history_index.is_some() => prompt.text = prompt.draft.clone(),
+            None => {}
+        }
+        prompt.history_index = index;
+        self.prompt = Some(prompt);
+    }
+
+    /// run the action of a prompt with the text typed into it
+    pub fn run_action(&mut self, action: Action, text: &str) {
+        match action {
+            Action::Replace if self.read_only => self.log = "buffer is read-only".to_string(),
+            Action::Replace => match parse_substitute(text) {
+                Some(substitute) if substitute.confirm => {
+                    self.confirm_replace = Some(ConfirmReplace {
+                        substitute,
+                        current: None,
+                        next: Vector2(0, 0),
+                        count: 0,
+                    });
+                    self.next_replace();
+                }
+                Some(substitute) => {
+                    let count = self.editor.replace_all(
+                        &substitute.find,
+                        &substitute.replace,
+                        substitute.global,
+                    );
+                    self.log = format!("replaced {} occurrences", count);
+                }
+                None => self.log = "usage: s/find/replace/[gc]".to_string(),
+            },
+            Action::ExCommand => self.run_ex_command(text),
+            Action::Open => self.open_file(Path::new(text.trim())),
+            Action::Search { regex, reverse } => {
+                // an empty search repeats the last one
+                let (text, regex) = match self.last_search {
+                    Some(ref last) if text.is_empty() => last.clone(),
+                    _ => (text.to_string(), regex),
+                };
+                self.search(&text, regex, reverse);
+                self.last_search = Some((text, regex));
+            }
+        }
+    }
+
+    /// run a command typed after `:`
+    pub fn run_ex_command(&mut self, command: &str) {
+        let command = command.trim();
+        let (name, argument) = match command.find(' ') {
+            Some(i) => (&command[..i], Some(command[i..].trim())),
+            None => (command, None),
+        };
+
+        match name {
+            "" => {}
+            "q" => self.quit(false),
+            "q!" => self.quit(true),
+            "w" => {
+                self.write_file(argument.map(Path::new));
+            }
+            "wq" => {
+                self.exit = self.write_file(argument.map(Path::new));
+                if let Some((_, ref mut quit)) = self.awaiting_overwrite {
+                    *quit = true;
+                }
+            }
+            "e" | "e!" => match argument {
+                Some(path) => self.open_file(Path::new(path)),
+                None => self.reload_file(name == "e!"),
+            },
+            "theme" => match argument.and_then(Theme::named) {
+                Some(theme) => self.render_opts.theme = theme,
+                None => self.log = "usage: theme dark|light".to_string(),
+            },
+            "colorcolumn" => match argument.map(str::parse::<i32>) {
+                Some(Ok(column)) if column > 0 => self.render_opts.color_column = Some(column),
+                None => self.render_opts.color_column = None,
+                _ => self.log = "usage: colorcolumn [column]".to_string(),
+            },
+            "stats" => self.log = self.stats(),
+            "%" => self.editor.select_all(),
+            "autoclose" => {
+                self.auto_close = !self.auto_close;
+                self.log = format!("auto close {}", if self.auto_close { "on" } else { "off" });
+            }
+            "view" => {
+                self.read_only = !self.read_only;
+                self.log = format!("read-only {}", if self.read_only { "on" } else { "off" });
+            }
+            "hardtabs" => {
+                self.hard_tabs = !self.hard_tabs;
+                self.log = format!("hard tabs {}", if self.hard_tabs { "on" } else { "off" });
+            }
+            "bn" => self.switch_buffer((self.current_buffer + 1) % self.buffers.len()),
+            "bp" => self
+                .switch_buffer((self.current_buffer + self.buffers.len() - 1) % self.buffers.len()),
+            "b" => match argument.and_then(|x| x.parse::<usize>().ok()) {
+                Some(n) if n > 0 => self.switch_buffer(n - 1),
+                _ => self.log = "usage: b <number>".to_string(),
+            },
+            _ if name.starts_with("s/") => self.run_action(Action::Replace, &command[2..]),
+            _ => match name.parse::<i32>() {
+                Ok(line) => {
+                    self.push_jump(self.editor.cursor_pos());
+                    self.editor
+                        .move_cursor_to(Position::Line(line.saturating_sub(1)));
+                    self.center_renderer();
+                }
+                Err(_) => self.log = format!("unknown command: {}", command),
+            },
+        }
+    }
+
+    /// the cursor's line and column counted from 1 and how far through the buffer its line is
+    pub fn cursor_status(&self) -> String {
+        let cursor = self.editor.cursor_pos();
+        let lines = self.editor.line_count().max(1) as i32;
+        format!(
+            "Ln {}, Col {} {:>3}%",
+            cursor.y() + 1,
+            cursor.x() + 1,
+            (cursor.y() + 1) * 100 / lines
+        )
+    }
+
+    /// the number of lines, words and characters in the selection or the buffer,
+    /// followed by the cursor's line and column
+    pub fn stats(&self) -> String {
+        let (lines, words, chars) = match (self.editor.selection_range(), self.editor.copy()) {
+            // the selection is copied as a cell per character, as char_count counts them
+            (Some((start, end)), Some(cells)) => {
+                (end.y() - start.y() + 1, count_words(&cells), cells.len())
+            }
+            _ => (
+                self.editor.line_count() as i32,
+                self.editor.word_count(),
+                self.editor.char_count(),
+            ),
+        };
+        let cursor = self.editor.cursor_pos();
+        format!(
+            "{}{} lines, {} words, {} characters; line {} of {}, column {}",
+            if self.editor.is_selecting() {
+                "selected: "
+            } else {
+                ""
+            },
+            lines,
+            words,
+            chars,
+            cursor.y() + 1,
+            self.editor.line_count(),
+            cursor.x() + 1
+        )
+    }
+
+    /// the name of the file being edited for the status line, marked with [+] when it has
+    /// unsaved changes, followed by its line ending
+    pub fn file_status(&self) -> String {
+        let name = match self.file_path {
+            Some(ref path) => path.display().to_string(),
+            None => "[No Name]".to_string(),
+        };
+        let modified = if self.read_only {
+            " [RO]"
+        } else if self.editor.is_modified() {
+            " [+]"
+        } else {
+            ""
+        };
+        let buffer = if self.buffers.len() > 1 {
+            format!("[{}/{}] ", self.current_buffer + 1, self.buffers.len())
+        } else {
+            String::new()
+        };
+        format!(
+            "{}{}{} [{}]",
+            buffer, name, modified, self.editor.line_ending
+        )
+    }
+
+    /// exit the editor, asking for confirmation first if the buffer has unsaved changes
+    /// unless `force` is set
+    pub fn quit(&mut self, force: bool) {
+        if force || !self.any_modified() {
+            self.exit = true;
+        } else {
+            self.awaiting_quit = true;
+            self.log = "unsaved changes — press y to quit, n to cancel".to_string();
+        }
+    }
+
+    /// write the buffer to `path`, or to the file being edited without a path.
+    /// the path becomes the file being edited
+    ///
+    /// # Returns
+    /// true if the file was written
+    pub fn write_file(&mut self, path: Option<&Path>) -> bool {
+        let path = match path.map(PathBuf::from).or_else(|| self.file_path.clone()) {
+            Some(path) => path,
+            None => {
+                self.log = "no file name".to_string();
+                return false;
+            }
+        };
+        if self.changed_on_disk(&path) {
+            self.awaiting_overwrite = Some((path, false));
+            self.log = "file changed on disk — overwrite? (y/n)".to_string();
+            return false;
+        }
+
+        match self.save_to_file(&path) {
+            Ok(_) => {
+                self.log = format!(
+                    "wrote {} lines to {}",
+                    self.editor.line_count(),
+                    path.display()
+                );
+                self.file_path = Some(path);
+                self.path_chosen = true;
+                self.update_highlighter();
+                self.save_positions();
+                true
+            }
+            Err(e) => {
+                self.error(format!("save failed: {}", e));
+                false
+            }
+        }
+    }
+
+    /// replace the buffer with the file at `path`, which becomes the file being edited.
+    /// unsaved changes are only discarded when `force` is set
+    pub fn open_file(&mut self, path: &Path) {
+        if path.as_os_str().is_empty() {
+            self.log = "no file name".to_string();
+            return;
+        }
+        if self.file_path.as_deref() == Some(path) {
+            return;
+        }
+        if let Some(i) = self
+            .buffers
+            .iter()
+            .position(|x| x.file_path.as_deref() == Some(path))
+        {
+            self.switch_buffer(i);
+            return;
+        }
+
+        match read_file(path) {
+            Ok(editor) => {
+                self.buffers.push(Buffer {
+                    editor,
+                    file_path: Some(path.to_path_buf()),
+                    path_chosen: true,
+                    last_known_mtime: modified_time(path),
+                    ..Buffer::new()
+                });
+                self.switch_buffer(self.buffers.len() - 1);
+                self.log = format!("opened {}", path.display());
+            }
+            Err(e) => self.error(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    /// edit the buffer at `index` in the buffer list, keeping the state of the current one
+    pub fn switch_buffer(&mut self, index: usize) {
+        if index >= self.buffers.len() {
+            self.log = format!("no buffer {}", index + 1);
+            return;
+        }
+        if index == self.current_buffer {
+            return;
+        }
+
+        let current = Buffer {
+            editor: std::mem::replace(&mut self.editor, Editor::new()),
+            file_path: self.file_path.take(),
+            path_chosen: std::mem::take(&mut self.path_chosen),
+            last_known_mtime: self.last_known_mtime.take(),
+            view: self.render_opts.view.location,
+            jump_list: std::mem::take(&mut self.jump_list),
+            jump_index: self.jump_index,
+        };
+        self.buffers[self.current_buffer] = current;
+
+        let buffer = std::mem::replace(&mut self.buffers[index], Buffer::new());
+        self.editor = buffer.editor;
+        self.file_path = buffer.file_path;
+        self.path_chosen = buffer.path_chosen;
+        self.last_known_mtime = buffer.last_known_mtime;
+        self.update_highlighter();
+        self.render_opts.view.location = buffer.view;
+        self.jump_list = buffer.jump_list;
+        self.jump_index = buffer.jump_index;
+        self.current_buffer = index;
+
+        self.search_highlight = false;
+        self.search_position = None;
+    }
+
+    /// move the cursor to where it was left in the file being edited, centering the view on it
+    pub fn restore_position(&mut self) {
+        let (state, file) = match (&self.positions_file, &self.file_path) {
+            (Some(state), Some(file)) => (state, file),
+            _ => return,
+        };
+        let location = std::fs::canonicalize(file)
+            .ok()
+            .and_then(|file| Positions::load(state).get(&file));
+        if let Some(location) = location {
+            self.editor.set_cursor(location);
+            self.center_renderer();
+        }
+    }
+
+    /// remember the cursor location in each open file in the positions file.
+    /// positions are only a convenience so failing to write them is ignored
+    pub fn save_positions(&self) {
+        let state = match self.positions_file {
+            Some(ref state) => state,
+            None => return,
+        };
+        let mut positions = Positions::load(state);
+        let buffers = self.buffers.iter().map(|x| (&x.file_path, &x.editor));
+        for (file, editor) in buffers.chain(Some((&self.file_path, &self.editor))) {
+            if let Some(file) = file.as_ref().and_then(|x| std::fs::canonicalize(x).ok()) {
+                positions.set(file, editor.cursor_pos());
+            }
+        }
+        let _ = positions.save(state);
+    }
+
+    /// whether any open buffer has unsaved changes
+    pub fn any_modified(&self) -> bool {
+        self.editor.is_modified() || self.buffers.iter().any(|x| x.editor.is_modified())
+    }
+
+    /// read the file being edited into the buffer again, keeping the cursor on the same line
+    /// and column where possible. unsaved changes are only discarded when `force` is set
+    pub fn reload_file(&mut self, force: bool) {
+        let path = match self.file_path {
+            Some(ref path) => path.clone(),
+            None => {
+                self.log = "no file name".to_string();
+                return;
+            }
+        };
+        if !force && self.editor.is_modified() {
+            self.log = "unsaved changes — use :e! to discard them".to_string();
+            return;
+        }
+
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                let cursor = self.editor.cursor_pos();
+                self.editor = Editor::from(text);
+                self.editor.set_cursor(cursor);
+                self.last_known_mtime = modified_time(&path);
+                self.log = format!("reloaded {}", path.display());
+            }
+            Err(e) => self.error(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    /// write the buffer to `path` and mark it as saved.
+    /// the buffer is written to a temporary file beside `path` which is then renamed over it,
+    /// so the file is never left half written
+    pub fn save_to_file(&mut self, path: &Path) -> std::io::Result<()> {
+        if self.changed_on_disk(path) {
+            return Err(std::io::Error::other("file changed on disk"));
+        }
+
+        // a symlink is followed so the file it points to is replaced rather than the link
+        let target = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let text = self.editor.to_string();
+        let name = target.file_name().unwrap_or_default().to_string_lossy();
+        let temp = target.with_file_name(format!(".{}.tmp", name));
+
+        if self.make_backup && target.exists() {
+            let backup = target.with_file_name(format!("{}{}", name, self.backup_suffix));
+            std::fs::copy(&target, backup)?;
+        }
+
+        std::fs::write(&temp, &text)?;
+        if let Ok(metadata) = std::fs::metadata(&target) {
+            // keep the permissions of the file being replaced
+            let _ = std::fs::set_permissions(&temp, metadata.permissions());
+        }
+        if std::fs::rename(&temp, &target).is_err() {
+            // renaming fails across filesystems, fall back to writing in place
+            let _ = std::fs::remove_file(&temp);
+            std::fs::write(&target, &text)?;
+        }
+
+        self.editor.set_modified(false);
+        self.last_known_mtime = modified_time(path);
+        Ok(())
+    }
+
+    /// whether `path` is the file being edited and has been modified since it was last
+    /// read or written
+    pub fn changed_on_disk(&self, path: &Path) -> bool {
+        self.file_path.as_deref() == Some(path)
+            && self.last_known_mtime.is_some()
+            && modified_time(path) != self.last_known_mtime
+    }
+
+    /// whether a search for `text` should match letters regardless of case.
+    /// with smart case an uppercase letter in the search makes it match case
+    fn search_ignores_case(&self, text: &str, regex: bool) -> bool {
+        let mut chars = text.chars();
+        let mut uppercase = false;
+        while let Some(c) = chars.next() {
+            // escapes such as \W in a regular expression are not letters to match
+            if regex && c == '\\' {
+                chars.next();
+            } else if c.is_uppercase() {
+                uppercase = true;
+            }
+        }
+        self.search_ignore_case && !(self.search_smart_case && uppercase)
+    }
+
+    /// move the cursor to the next occurrence of `text` after it, or before it if `reverse` is set
+    fn search(&mut self, text: &str, regex: bool, reverse: bool) {
+        let pattern = match self.search_pattern(text, regex) {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                self.error(e.to_string());
+                return;
+            }
+        };
+
+        let cursor = self.editor.cursor_pos();
+        let from = if reverse { cursor } else { cursor.add((1, 0)) };
+        self.search_highlight = true;
+        match self
+            .editor
+            .search_wrapping(&pattern, from, reverse, self.search_wrap)
+        {
+            Some((location, wrapped)) => {
+                self.push_jump(cursor);
+                self.editor.set_cursor(location);
+                if !self.cursor_in_view() {
+                    self.center_renderer();
+                }
+                self.log = match (wrapped, reverse) {
+                    (true, false) => "search wrapped to top".to_string(),
+                    (true, true) => "search wrapped to bottom".to_string(),
+                    _ => format!("found {}", text),
+                };
+            }
+            None => self.log = format!("{} not found", text),
+        }
+
+        // count the matches up to and including the one at the cursor
+        let matches = self.editor.search_all(&pattern);
+        let cursor = self.editor.cursor_pos();
+        let current = matches.iter().filter(|&&x| x <= cursor).count();
+        self.search_position = Some((current, matches.len()));
+    }
+
+    /// compile the pattern matched by a search for `text`
+    fn search_pattern(&self, text: &str, regex: bool) -> pattern::Result<Pattern> {
+        let ignore_case = self.search_ignores_case(text, regex);
+        let pattern = if regex {
+            Pattern::new(text)?
+        } else {
+            Pattern::new(&pattern::escape(text))?
+        };
+        Ok(pattern.ignore_case(ignore_case))
+    }
+
+    /// return the matches of the last search on the lines in view
+    fn search_matches(&self) -> Vec<(Vector2, Vector2)> {
+        // the text being typed into a search prompt is previewed
+        let (text, regex) = match (&self.prompt, &self.last_search) {
+            (
+                Some(Prompt {
+                    action: Action::Search { regex, .. },
+                    text,
+                    ..
+                }),
+                _,
+            ) if !text.is_empty() => (text, *regex),
+            (_, Some((text, regex))) if self.search_highlight => (text, *regex),
+            _ => return Vec::new(),
+        };
+        let view = &self.render_opts.view;
+        match self.search_pattern(text, regex) {
+            Ok(pattern) => self
+                .editor
+                .find_matches(&pattern, view.y()..view.y() + view.height),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// select the next occurrence of a confirmed replace, finishing it when there are no more
+    fn next_replace(&mut self) {
+        let state = match self.confirm_replace {
+            Some(ref mut state) => state,
+            None => return,
+        };
+        match self
+            .editor
+            .search(&state.substitute.find, state.next, false)
+        {
+            Some(start) => {
+                state.current = Some(start);
+                let end = start.add((state.substitute.find.chars().count() as i32, 0));
+                self.editor.begin_select_at(start);
+                self.editor.set_cursor(end);
+                self.log = format!("replace with {}? (y/n/a/q)", state.substitute.replace);
+                self.center_renderer();
+            }
+            None => self.finish_replace(),
+        }
+    }
+
+    /// respond to the current occurrence of a confirmed replace.
+    /// `y` replaces it, `n` skips it, `a` replaces every remaining occurrence and `q` stops
+    pub fn answer_replace(&mut self, answer: char) {
+        match answer {
+            'y' | 'n' => {
+                self.advance_replace(answer == 'y');
+                self.next_replace();
+            }
+            'a' => {
+                while matches!(self.confirm_replace, Some(ref x) if x.current.is_some()) {
+                    self.advance_replace(true);
+                    self.next_replace();
+                }
+            }
+            'q' => self.finish_replace(),
+            _ => {}
+        }
+    }
+
+    /// move past the current occurrence of a confirmed replace, replacing it if `replace` is set
+    fn advance_replace(&mut self, replace: bool) {
+        let state = match self.confirm_replace {
+            Some(ref mut state) => state,
+            None => return,
+        };
+        let start = match state.current.take() {
+            Some(x) => x,
+            None => return,
+        };
+
+        let mut skip = 1;
+        if replace {
+            let end = start.add((state.substitute.find.chars().count() as i32, 0));
+            self.editor.clear_selection();
+            self.editor
+                .replace_range(start, end, &state.substitute.replace);
+            state.count += 1;
+            skip = state.substitute.replace.chars().count() as i32;
+        }
+
+        // only the first occurrence on each line is replaced without the global flag
+        state.next = if state.substitute.global {
+            start.add((skip, 0))
+        } else {
+            Vector2(0, start.y() + 1)
+        };
+    }
+
+    /// end a confirmed replace, reporting the number of replacements made
+    fn finish_replace(&mut self) {
+        if let Some(state) = self.confirm_replace.take() {
+            self.editor.clear_selection();
+            self.log = format!("replaced {} occurrences", state.count);
+        }
+    }
+
+    /// write a typed character at the cursor. with `auto_close` an opening bracket or quote is
+    /// followed by its closing one, and a closing one typed just before the same character
+    /// steps over it instead
+    pub fn type_char(&mut self, x: char) {
+        let cursor = self.editor.cursor_pos();
+        let next = self.editor.get_cell(cursor).map(|x| x.char);
+        let is_closing = matches!(x, ')' | ']' | '}' | '"' | '\'');
+        if self.auto_close && is_closing && next == Some(x) {
+            self.editor.move_cursor((1, 0));
+            return;
+        }
+
+        // a quote after a letter is an apostrophe rather than the start of a string
+        let previous = self.editor.get_cell(cursor.add((-1, 0))).map(|x| x.char);
+        let after_word = cursor.x() > 0 && previous.is_some_and(|c| c.is_alphanumeric());
+        self.editor.write(x);
+        match closing_pair(x) {
+            Some(close) if self.auto_close && !(close == x && after_word) => {
+                self.editor.write_at(self.editor.cursor_pos(), close);
+            }
+            _ => {}
+        }
+    }
+
+    /// return the text Tab types at the cursor, a tab character with `hard_tabs`
+    /// and otherwise the spaces reaching the next tab stop
+    pub fn tab_text(&self) -> String {
+        if self.hard_tabs {
+            return "\t".to_string();
+        }
+        " ".repeat(self.editor.tab_stop_width(self.render_opts.tab_width))
+    }
+
+    /// record a typed character as the last change,
+    /// extending the previous insert if `continue_change` is set
+    fn record_insert(&mut self, x: char, continue_change: bool) {
+        match self.last_change {
+            Some(Change::Insert(ref mut text)) if continue_change => text.push(x),
+            _ => self.last_change = Some(Change::Insert(x.to_string())),
+        }
+        self.change_in_progress = true;
+    }
+
+    /// record deleted characters as the last change,
+    /// extending the previous deletion if `continue_change` is set
+    fn record_delete(&mut self, count: usize, continue_change: bool) {
+        match self.last_change {
+            Some(Change::Delete(ref mut n)) if continue_change => *n += count,
+            _ => self.last_change = Some(Change::Delete(count)),
+        }
+        self.change_in_progress = true;
+    }
+
+    /// apply a change at the current cursor location
+    pub fn replay_change(&mut self, change: &Change) {
+        match change {
+            Change::Insert(text) => {
+                for x in text.chars() {
+                    self.editor.write(x);
+                }
+            }
+            Change::Delete(count) => {
+                for _ in 0..*count {
+                    self.editor.delete();
+                }
+            }
+            Change::Replace(x) => {
+                self.editor.replace_char(*x);
+            }
+        }
+    }
+
+    /// begin recording the keys pressed into the register `name`
+    pub fn start_recording(&mut self, name: char) {
+        self.recording = Some((name, Vec::new()));
+        self.log = format!("recording into {}", name);
+    }
+
+    /// stop recording keys and keep them in the register being recorded into
+    pub fn stop_recording(&mut self) {
+        if let Some((name, keys)) = self.recording.take() {
+            self.log = format!("recorded {} keys into {}", keys.len(), name);
+            self.macros.insert(name, keys);
+        }
+    }
+
+    /// process the keys recorded into the register `name` `count` times, stopping early if
+    /// the cursor could not move because it reached the edge of the buffer
+    ///
+    /// # Returns
+    /// The number of times the keys were replayed in full
+    pub fn replay_macro(&mut self, name: char, count: u32) -> u32 {
+        use KeyEvent::*;
+
+        let keys = match self.macros.get(&name) {
+            Some(keys) => keys.clone(),
+            None => {
+                self.log = format!("nothing recorded into {}", name);
+                return 0;
+            }
+        };
+        if self.replaying {
+            self.log = "macros cannot replay other macros".to_string();
+            return 0;
+        }
+        self.last_macro = Some(name);
+
+        self.replaying = true;
+        let mut replayed = 0;
+        'replay: for _ in 0..count {
+            for key in &keys {
+                let previous = self.editor.cursor_pos();
+                let motion = matches!(key, Up | Down | Left | Right);
+                self.process_key_event(*key);
+                if motion && self.editor.cursor_pos() == previous {
+                    break 'replay;
+                }
+            }
+            replayed += 1;
+        }
+        self.replaying = false;
+        self.clear_render_hints();
+        replayed
+    }
+
+    /// name the cursor's location with the letter `name`
+    pub fn set_mark(&mut self, name: char) {
+        if name.is_ascii_lowercase() {
+            self.editor.set_mark(name);
+            self.log = format!("set mark {}", name);
+        } else {
+            self.log = "marks are named a to z".to_string();
+        }
+    }
+
+    /// move the cursor to the mark `name`
+    ///
+    /// # Returns
+    /// false if the mark is not set
+    pub fn jump_to_mark(&mut self, name: char) -> bool {
+        match self.editor.mark(name) {
+            Some(location) => {
+                self.editor.set_cursor(location);
+                true
+            }
+            None => {
+                self.log = format!("mark {} is not set", name);
+                false
+            }
+        }
+    }
+
+    /// highlight the file being edited by its extension, a file type without a
+    /// highlighter and a buffer without a file are left plain
+    pub fn update_highlighter(&mut self) {
+        self.highlighter = self.file_path.as_deref().and_then(highlight::for_path);
+    }
+
+    /// the prefix lines are commented out with in the file being edited
+    pub fn comment_prefix(&self) -> String {
+        self.file_path
+            .as_ref()
+            .and_then(|path| path.extension())
+            .and_then(|x| self.comment_prefixes.get(&*x.to_string_lossy()))
+            .map_or(DEFAULT_COMMENT_PREFIX.to_string(), String::clone)
+    }
+
+    /// apply an operator to `count` lines from the cursor's line, d cuts them into a register
+    /// and leaves the cursor on the first non-blank character of the line after them,
+    /// y copies them
+    pub fn apply_line_operator(&mut self, operator: char, count: u32, register: Option<char>) {
+        if self.read_only && operator != 'y' {
+            self.log = "buffer is read-only".to_string();
+            return;
+        }
+        let first = self.editor.cursor_pos().y();
+        let last = first + count.max(1) as i32 - 1;
+        let (cells, done) = match operator {
+            'd' => {
+                let cells = self.editor.cut_lines(first, last);
+                self.editor.move_cursor_to(Position::FirstNonBlank);
+                (cells, "deleted")
+            }
+            'y' => (self.editor.copy_lines(first, last), "yanked"),
+            x => {
+                self.log = format!("unknown operator: {}", x);
+                return;
+            }
+        };
+
+        let text: String = cells.iter().map(|x| x.char).collect();
+        let lines = text.matches('\n').count().max(1);
+        match self.store_register(register, text, true) {
+            Ok(_) => self.log = format!("{} {} lines", done, lines),
+            Err(e) => self.error(e.to_string()),
+        }
+    }
+
+    /// wrap the selection in the pair of delimiters `delimiter` belongs to,
+    /// a line selection is wrapped from the start of its first line to the end of its last
+    pub fn surround_selection(&mut self, delimiter: char) {
+        let (open, close) = match delimiter {
+            '(' | ')' => ('(', ')'),
+            '[' | ']' => ('[', ']'),
+            '{' | '}' => ('{', '}'),
+            '<' | '>' => ('<', '>'),
+            '"' | '\'' | '`' => (delimiter, delimiter),
+            _ => {
+                self.log = format!("cannot surround with {}", delimiter);
+                return;
+            }
+        };
+        let (start, end) = match self.editor.selection_range() {
+            Some(range) => range,
+            None => {
+                self.log = "nothing selected".to_string();
+                return;
+            }
+        };
+
+        if self.editor.selection_kind() == SelectionKind::Line {
+            let last_len = self.editor.get_row(end.y()).map_or(0, |row| row.len());
+            self.editor.surround(
+                Vector2(0, start.y()),
+                Vector2(last_len as i32, end.y()),
+                open,
+                close,
+            );
+        } else {
+            self.editor.surround(start, end, open, close);
+        }
+    }
+
+    /// apply an operator to a text object named by three keys, the operator, i for inside or a for
+    /// around, and the kind of object. d and c cut the object into a register, y copies it
+    /// and v selects it
+    pub fn apply_text_object(&mut self, keys: &str, register: Option<char>) {
+        let keys: Vec<char> = keys.chars().collect();
+        if self.read_only && matches!(keys.first(), Some('d') | Some('c')) {
+            self.log = "buffer is read-only".to_string();
+            return;
+        }
+        let around = match keys.get(1) {
+            Some('i') => false,
+            Some('a') => true,
+            _ => {
+                self.log = "text objects are i or a followed by an object".to_string();
+                return;
+            }
+        };
+        let range = match keys.get(2) {
+            Some('w') => self.editor.word_object(around),
+            Some('(') | Some(')') | Some('b') => self.editor.delimiter_object('(', ')', around),
+            Some('[') | Some(']') => self.editor.delimiter_object('[', ']', around),
+            Some('{') | Some('}') | Some('B') => self.editor.delimiter_object('{', '}', around),
+            Some(&x @ '"') | Some(&x @ '\'') | Some(&x @ '`') => {
+                self.editor.delimiter_object(x, x, around)
+            }
+            _ => {
+                self.log = format!(
+                    "unknown text object: {}",
+                    keys[2..].iter().collect::<String>()
+                );
+                return;
+            }
+        };
+        let (start, end) = match range {
+            Some(range) => range,
+            None => {
+                self.log = "no text object at the cursor".to_string();
+                return;
+            }
+        };
+
+        let text = match keys[0] {
+            'd' | 'c' => self.editor.cut_range(start, end),
+            'y' => self.editor.copy_range(start, end),
+            'v' => {
+                self.editor.clear_selection();
+                self.editor.begin_select_at(start);
+                self.editor.set_cursor(end);
+                return;
+            }
+            x => {
+                self.log = format!("unknown operator: {}", x);
+                return;
+            }
+        };
+        self.editor.set_cursor(start);
+        let text = text.iter().map(|x| x.char).collect();
+        if let Err(e) = self.store_register(register, text, false) {
+            self.error(e.to_string());
+        }
+    }
+
+    /// copy the selection into a register and the clipboard, ending the selection.
+    /// the unnamed register is always updated
+    pub fn copy_selection(&mut self, register: Option<char>) {
+        let text = match self.editor.copy() {
+            Some(cells) => cells.iter().map(|x| x.char).collect::<String>(),
+            None => {
+                self.log = "nothing selected".to_string();
+                return;
+            }
+        };
+        let linewise = self.editor.selection_kind() == SelectionKind::Line;
+        self.editor.clear_selection();
+
+        match self.store_register(register, text, linewise) {
+            Ok(_) => self.log = "copied selection".to_string(),
+            Err(e) => self.error(e.to_string()),
+        }
+    }
+
+    /// cut the selection into a register and the clipboard, ending the selection.
+    /// the unnamed register is always updated
+    pub fn cut_selection(&mut self, register: Option<char>) {
+        let linewise = self.editor.selection_kind() == SelectionKind::Line;
+        let start = self
+            .editor
+            .selection_range()
+            .filter(|_| self.editor.selection_kind() == SelectionKind::Char)
+            .map(|x| x.0);
+        let text = match self.editor.cut() {
+            Some(cells) => cells.iter().map(|x| x.char).collect::<String>(),
+            None => {
+                self.log = "nothing selected".to_string();
+                return;
+            }
+        };
+        self.editor.clear_selection();
+        // the cursor is left where a selection of characters began
+        if let Some(start) = start {
+            self.editor.set_cursor(start);
+        }
+
+        match self.store_register(register, text, linewise) {
+            Ok(_) => self.log = "cut selection".to_string(),
+            Err(e) => self.error(e.to_string()),
+        }
+    }
+
+    /// store text in a register and the clipboard, the unnamed register is always updated
+    fn store_register(
+        &mut self,
+        register: Option<char>,
+        text: String,
+        linewise: bool,
+    ) -> Result<(), clipboard::Error> {
+        let copied = Register {
+            text: text.clone(),
+            linewise,
+        };
+        if let Some(name) = register {
+            self.registers.insert(name, copied.clone());
+        }
+        self.registers.insert(UNNAMED_REGISTER, copied);
+
+        self.clipboard.copy(text)
+    }
+
+    /// insert the contents of a register `count` times at the cursor, or on new lines below
+    /// the cursor if the register holds whole lines.
+    /// without a register the clipboard is used, falling back to the unnamed register
+    ///
+    /// # Returns
+    /// The start and end of the inserted text
+    pub fn paste(&mut self, register: Option<char>, count: usize) -> Option<(Vector2, Vector2)> {
+        let unnamed = self.registers.get(&UNNAMED_REGISTER).cloned();
+        let pasted = match register {
+            Some(name) => self.registers.get(&name).cloned(),
+            None => match self.clipboard.paste() {
+                // the clipboard only holds text, the unnamed register knows if it was linewise
+                Ok(text) => match unnamed {
+                    Some(x) if x.text == text => Some(x),
+                    _ => Some(Register {
+                        text,
+                        linewise: false,
+                    }),
+                },
+                Err(_) => unnamed,
+            },
+        };
+
+        let pasted = match pasted {
+            Some(x) => x,
+            None => {
+                self.log = "nothing to paste".to_string();
+                return None;
+            }
+        };
+
+        let text = pasted.text.replace('\r', "");
+        if pasted.linewise {
+            // every copy of the lines ends with a newline so the next one starts on its own line
+            let lines = match text.strip_suffix('\n') {
+                Some(lines) => lines,
+                None => &text,
+            };
+            let text = format!("{}\n", lines).repeat(count);
+
+            // open a new line below the cursor and leave the cursor at its beginning
+            let line = self.editor.cursor_pos().y();
+            let start = self
+                .editor
+                .set_cursor((self.editor.line_len() as i32, line));
+            self.editor.write('\n');
+            for x in text.strip_suffix('\n').unwrap_or(&text).chars() {
+                self.editor.write(x);
+            }
+            let end = self.editor.cursor_pos();
+            self.editor.set_cursor((0, line + 1));
+            Some((start, end))
+        } else {
+            let start = self.editor.cursor_pos();
+            for x in text.repeat(count).chars() {
+                self.editor.write(x);
+            }
+            Some((start, self.editor.cursor_pos()))
+        }
+    }
+
+    /// scroll the view so the cursor's line is in its center for `z`, near its top for `t`
+    /// or near its bottom for `b`, leaving `scroll_off` lines around the cursor
+    pub fn align_view(&mut self, place: char) {
+        let view = &mut self.render_opts.view;
+        let scroll_off = self.scroll_off.min((view.height - 1) / 2).max(0);
+        let y = self.editor.cursor_pos().y();
+        view.location.1 = match place {
+            'z' => y - view.height / 2,
+            't' => y - scroll_off,
+            'b' => y - view.height + 1 + scroll_off,
+            _ => return,
+        }
+        .max(0);
+    }
+
+    /// center the view on the cursor location
+    pub fn center_renderer(&mut self) {
+        self.render_opts.view.location.1 =
+            self.editor.cursor_pos().y() - (self.render_opts.view.height / 2);
+    }
+
+    pub fn clear_render_hints(&mut self) {
+        self.render_break_line_hint = false;
+        self.render_line_hint = None;
+    }
+
+    /// place the cursor after moving it from `previous`,
+    /// rendering the screen again if anything drawn depends on the cursor
+    pub fn cursor_moved(&mut self, previous: Vector2) {
+        let follows_cursor = self.render_opts.highlight_current_line
+            || matches!(
+                self.render_opts.line_numbers,
+                LineNumberMode::Relative | LineNumberMode::Hybrid
+            );
+
+        let line_changed = previous.y() != self.editor.cursor_pos().y();
+        let scrolled = self.scroll_to_cursor();
+        if scrolled || self.editor.is_selecting() || (follows_cursor && line_changed) {
+            self.render();
+        } else {
+            self.update_cursor_pos();
+        }
     }
 
-    /// render the screen to crossterm.
-    /// if self.render_line_hint is not None, only that line will be rendered
-    pub fn render(&mut self) {
-        self.update_view_size().unwrap();
+    /// place the cursor after a jump from `previous`,
+    /// centering the view on the cursor if it has left the screen
+    pub fn cursor_jumped(&mut self, previous: Vector2) {
+        self.push_jump(previous);
+        if !self.cursor_in_view() {
+            self.center_renderer();
+            self.render();
+        } else {
+            self.cursor_moved(previous);
+        }
+    }
 
-        // render a single line if the line hint is not None
-        if let Some(line) = self.render_line_hint {
-            self.render_line(line);
-            return;
+    /// add the location the cursor jumped from to the jump list, dropping the entries after
+    /// the one returned to with ^P and the oldest entry when the list is full
+    pub fn push_jump(&mut self, location: Vector2) {
+        self.jump_list.truncate(self.jump_index);
+        if self.jump_list.last() != Some(&location) {
+            self.jump_list.push(location);
+        }
+        if self.jump_list.len() > JUMP_LIST_LENGTH {
+            self.jump_list.remove(0);
+        }
+        self.jump_index = self.jump_list.len();
+    }
+
+    /// move the cursor back to the previous location in the jump list.
+    /// the first step back remembers the cursor's location so ^N can return to it
+    ///
+    /// # Returns
+    /// false if there is no older location
+    pub fn jump_back(&mut self) -> bool {
+        let cursor = self.editor.cursor_pos();
+        if self.jump_index == self.jump_list.len() {
+            if self.jump_list.last() != Some(&cursor) {
+                self.jump_list.push(cursor);
+            }
+            self.jump_index = self.jump_list.len().saturating_sub(1);
+        }
+        if self.jump_index == 0 {
+            self.log = "at the oldest jump".to_string();
+            return false;
         }
+        self.jump_index -= 1;
+        self.editor.set_cursor(self.jump_list[self.jump_index]);
+        true
+    }
 
-        let text = StringRenderer::new().render(&self.editor, self.render_opts);
+    /// move the cursor forward to the next location in the jump list
+    ///
+    /// # Returns
+    /// false if there is no newer location
+    pub fn jump_forward(&mut self) -> bool {
+        if self.jump_index + 1 >= self.jump_list.len() {
+            self.log = "at the newest jump".to_string();
+            return false;
+        }
+        self.jump_index += 1;
+        self.editor.set_cursor(self.jump_list[self.jump_index]);
+        true
+    }
 
-        let mut stdout = std::io::stdout();
-        stdout.execute(MoveTo(0, 0)).unwrap();
-        write!(
-            &mut stdout,
-            "{}[F1 to display help ] {:?}{}",
-            text, self.render_opts, self.log
-        )
-        .unwrap();
+    /// scroll the view so the cursor's line is at least `scroll_off` lines from its top and bottom,
+    /// and the cursor's column is `side_scroll_off` columns from its sides
+    ///
+    /// # Returns
+    /// true if the view moved
+    pub fn scroll_to_cursor(&mut self) -> bool {
+        let cursor = self.editor.cursor_pos();
+        let column = self.render_opts.screen_column(&self.editor, cursor);
+        let width = self.render_opts.view.width - self.render_opts.gutter_width(&self.editor);
+        let view = &mut self.render_opts.view;
+        let previous = view.location;
 
-        self.update_cursor_pos();
+        let scroll_off = self.scroll_off.min((view.height - 1) / 2).max(0);
+        if cursor.y() < view.location.1 + scroll_off {
+            view.location.1 = (cursor.y() - scroll_off).max(0);
+        } else if cursor.y() > view.location.1 + view.height - 1 - scroll_off {
+            view.location.1 = cursor.y() - view.height + 1 + scroll_off;
+        }
+
+        if self.render_opts.wrap {
+            // long lines take several rows, scroll down until the cursor's row is on the screen
+            view.location.0 = 0;
+            while self.render_opts.view.location.1 < cursor.y()
+                && self
+                    .render_opts
+                    .wrapped_position(&self.editor, cursor)
+                    .is_none()
+            {
+                self.render_opts.view.location.1 += 1;
+            }
+            return self.render_opts.view.location != previous;
+        }
+
+        let side_scroll_off = self.side_scroll_off.min((width - 1) / 2).max(0);
+        if column < view.location.0 + side_scroll_off {
+            view.location.0 = (column - side_scroll_off).max(0);
+        } else if column > view.location.0 + width - 1 - side_scroll_off {
+            view.location.0 = column - width + 1 + side_scroll_off;
+        }
+
+        view.location != previous
     }
 
-    pub fn clear_render_hints(&mut self) {
-        self.render_break_line_hint = false;
-        self.render_line_hint = None;
+    /// toggle drawing long lines across several rows
+    pub fn toggle_wrap(&mut self) {
+        self.render_opts.wrap = !self.render_opts.wrap;
+        self.scroll_to_cursor();
+        self.log = format!("wrap {}", if self.render_opts.wrap { "on" } else { "off" });
+    }
+
+    /// whether the cursor's line is on the screen
+    fn cursor_in_view(&self) -> bool {
+        let view = &self.render_opts.view;
+        let y = self.editor.cursor_pos().y();
+        y >= view.y() && y < view.y() + view.height
     }
 
     pub fn update_cursor_pos(&self) {
-        if self.render_opts.view.contains(self.editor.cursor_pos()) {
+        if self.replaying {
+            return;
+        }
+        let gutter = self.render_opts.gutter_width(&self.editor);
+        if self.render_opts.wrap {
+            if let Some(Vector2(x, y)) = self
+                .render_opts
+                .wrapped_position(&self.editor, self.editor.cursor_pos())
+            {
+                std::io::stdout()
+                    .execute(MoveTo((x + gutter) as u16, y as u16))
+                    .unwrap();
+            }
+            return;
+        }
+
+        // the column the cursor is drawn at, tabs may be drawn across several columns
+        let cursor = self.editor.cursor_pos();
+        let cursor = Vector2(
+            self.render_opts.screen_column(&self.editor, cursor),
+            cursor.y(),
+        );
+
+        if self.render_opts.view.contains(cursor) {
             // place the cursor over the current character
             let x = self.render_opts.view.x();
             let y = self.render_opts.view.y();
 
             // obtain the position of the cursor relative to the screen
-            let real_x = self.editor.cursor_pos().x() - x;
-            let real_y = self.editor.cursor_pos().y() - y;
+            let real_x = cursor.x() - x + gutter;
+            let real_y = cursor.y() - y;
 
-            std::io::stdout()
-                .execute(MoveTo(real_x as u16, real_y as u16))
-                .unwrap();
+            // the gutter may push the cursor past the edge of the view
+            if real_x < self.render_opts.view.width {
+                std::io::stdout()
+                    .execute(MoveTo(real_x as u16, real_y as u16))
+                    .unwrap();
+            }
         }
     }
 
     /// render only a single line of the editor
     pub fn render_line(&mut self, line: i32) {
+        if self.replaying {
+            return;
+        }
         let ycp = line;
         let y = ycp - self.render_opts.view.location.y();
         if self.render_opts.view.contains(Vector2(0, ycp)) {
@@ -267,9 +3129,14 @@ where
             let text = StringRenderer {
                 line_hint: Some(line),
                 break_on_line_end: self.render_break_line_hint,
+                highlighter: self.highlighter.clone(),
+                search_matches: self.search_matches(),
             }
             .render(&self.editor, self.render_opts);
             print!("{}", text);
+            if let Some(drawn) = self.last_frame.get_mut(y as usize) {
+                drawn.clear();
+            }
             self.update_cursor_pos();
             self.clear_render_hints();
         } else {
@@ -277,14 +3144,1138 @@ where
             // the line is not in view, render normally
             self.render();
         }
-
     }
 
     /// update the view size for the renderer
-    pub fn update_view_size(&mut self) -> crossterm::Result<()> {
+    ///
+    /// # Returns
+    /// true if the size of the terminal changed
+    pub fn update_view_size(&mut self) -> crossterm::Result<bool> {
         let (cols, rows) = terminal::size()?;
-        self.render_opts.view.width = cols as i32;
-        self.render_opts.view.height = rows as i32 - 1;
-        Ok(())
+        Ok(self.resize(cols as i32, rows as i32 - 1))
+    }
+
+    /// change the size of the view, scrolling it so the cursor stays in view after shrinking
+    ///
+    /// # Returns
+    /// true if the size changed
+    pub fn resize(&mut self, width: i32, height: i32) -> bool {
+        if (width, height) == (self.render_opts.view.width, self.render_opts.view.height) {
+            return false;
+        }
+        self.render_opts.view.width = width;
+        self.render_opts.view.height = height;
+        // the terminal may have rearranged what was drawn
+        self.last_frame.clear();
+        self.scroll_to_cursor();
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::clipboard::MockClipboard;
+    use crate::editor::LineEnding;
+
+    #[test]
+    fn test_application_copy_paste() {
+        let mut app = Application::new(Editor::from("hello\nworld"), MockClipboard::new());
+
+        // copy a selection into a named register
+        app.editor.begin_select();
+        app.editor.set_cursor((5, 0));
+        app.copy_selection(Some('a'));
+
+        assert_eq!(app.clipboard.paste().unwrap(), "hello");
+        assert_eq!(app.registers[&'a'].text, "hello");
+        assert!(!app.editor.is_selecting());
+
+        app.paste(None, 1);
+        assert_eq!(app.editor.to_string(), "hellohello\nworld");
+
+        // copied lines are pasted on a new line below the cursor
+        app.editor.set_cursor((2, 1));
+        app.editor.begin_line_select();
+        app.copy_selection(None);
+        app.paste(None, 1);
+        assert_eq!(app.editor.to_string(), "hellohello\nworld\nworld");
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 2));
+
+        app.paste(Some('a'), 1);
+        assert_eq!(app.editor.to_string(), "hellohello\nworld\nhelloworld");
+        // a count pastes the whole register that many times, lines and all
+        let mut app = Application::new(Editor::from("x\ny"), MockClipboard::new());
+        app.registers.insert(
+            'b',
+            Register {
+                text: "a\nb\n".to_string(),
+                linewise: true,
+            },
+        );
+        app.paste(Some('b'), 2);
+        assert_eq!(app.editor.to_string(), "x\na\nb\na\nb\ny");
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 1));
+        app.registers.insert(
+            'c',
+            Register {
+                text: "ab".to_string(),
+                linewise: false,
+            },
+        );
+        app.paste(Some('c'), 3);
+        assert_eq!(app.editor.to_string(), "x\nabababa\nb\na\nb\ny");
+    }
+
+    #[test]
+    fn test_parse_substitute() {
+        let substitute = parse_substitute("a\\/b/c/gc").unwrap();
+        assert_eq!(substitute.find, "a/b");
+        assert_eq!(substitute.replace, "c");
+        assert!(substitute.global && substitute.confirm);
+
+        let substitute = parse_substitute("a/").unwrap();
+        assert_eq!(
+            (substitute.find.as_str(), substitute.replace.as_str()),
+            ("a", "")
+        );
+        assert!(!substitute.global && !substitute.confirm);
+
+        assert_eq!(parse_substitute("a"), None);
+        assert_eq!(parse_substitute("a/b/x"), None);
+    }
+
+    #[test]
+    fn test_application_replace() {
+        let mut app = Application::new(Editor::from("aa\na"), MockClipboard::new());
+        app.run_action(Action::Replace, "a/b/g");
+        assert_eq!(app.editor.to_string(), "bb\nb");
+        assert_eq!(app.log, "replaced 3 occurrences");
+    }
+
+    fn search(app: &mut Application<MockClipboard>, text: &str, regex: bool, reverse: bool) {
+        app.run_action(Action::Search { regex, reverse }, text);
+    }
+
+    #[test]
+    fn test_application_search() {
+        let mut app = Application::new(Editor::from("ab a1\na22"), MockClipboard::new());
+        search(&mut app, r"a\d+", true, false);
+        assert_eq!(app.editor.cursor_pos(), Vector2(3, 0));
+
+        // an empty search repeats the last search
+        search(&mut app, "", false, false);
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 1));
+
+        // every match in view is highlighted after a search
+        app.render_opts.view.location = Vector2(0, 0);
+        app.render_opts.view.height = 2;
+        assert_eq!(
+            app.search_matches(),
+            vec![
+                (Vector2(3, 0), Vector2(5, 0)),
+                (Vector2(0, 1), Vector2(3, 1))
+            ]
+        );
+        assert_eq!(app.search_position, Some((2, 2)));
+
+        // searches wrap around the ends of the buffer
+        search(&mut app, "", false, false);
+        assert_eq!(app.editor.cursor_pos(), Vector2(3, 0));
+        assert_eq!(app.log, "search wrapped to top");
+        search(&mut app, "", false, true);
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 1));
+        assert_eq!(app.log, "search wrapped to bottom");
+
+        search(&mut app, "x", false, false);
+        assert_eq!(app.search_position, Some((0, 0)));
+
+        // lowercase searches ignore case unless that is turned off
+        let mut app = Application::new(Editor::from("a\nA"), MockClipboard::new());
+        search(&mut app, "a", false, false);
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 1));
+        app.editor.set_cursor((0, 0));
+        search(&mut app, "\\w", true, false);
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 1));
+        app.editor.set_cursor((0, 0));
+        app.search_ignore_case = false;
+        app.search_wrap = false;
+        search(&mut app, "a", false, false);
+        assert_eq!(app.log, "a not found");
+
+        search(&mut app, "a(", true, false);
+        assert_eq!(app.log, "invalid pattern: unclosed group");
+        search(&mut app, "a(", false, false);
+        assert_eq!(app.log, "a( not found");
+    }
+
+    #[test]
+    fn test_application_preview_search() {
+        let mut app = Application::new(Editor::from("abc\nab\na"), MockClipboard::new());
+        app.editor.set_cursor((1, 2));
+        app.open_prompt(Action::Search {
+            regex: false,
+            reverse: false,
+        });
+
+        let type_text = |app: &mut Application<MockClipboard>, text: &str| {
+            app.prompt.as_mut().unwrap().text = text.to_string();
+            app.preview_search();
+        };
+        type_text(&mut app, "ab");
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 0));
+        type_text(&mut app, "abc");
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 0));
+        type_text(&mut app, "abx");
+        assert_eq!(app.editor.cursor_pos(), Vector2(1, 2));
+
+        type_text(&mut app, "ab");
+        app.cancel_prompt();
+        assert_eq!(app.editor.cursor_pos(), Vector2(1, 2));
+        assert_eq!(app.prompt, None);
+    }
+
+    #[test]
+    fn test_application_ex_command() {
+        let mut app = Application::new(Editor::from("a\nb\nc"), MockClipboard::new());
+        app.run_ex_command("2");
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 1));
+        // lines far out of range are clamped rather than overflowing
+        app.run_ex_command("-2147483648");
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 0));
+        app.pending_count = Some(u32::MAX);
+        app.process_key_event(KeyEvent::Alt('G'));
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 2));
+
+        app.run_ex_command("x");
+        assert_eq!(app.log, "unknown command: x");
+        app.run_ex_command("w");
+        assert_eq!(app.log, "no file name");
+
+        app.run_ex_command("s/b/d/");
+        assert_eq!(app.editor.to_string(), "a\nd\nc");
+
+        let path = std::env::temp_dir().join("rust_ed_test_ex_command.txt");
+        app.run_ex_command(&format!("wq {}", path.display()));
+        assert!(app.exit);
+        assert_eq!(app.file_path, Some(path.clone()));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\nd\nc");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_application_open() {
+        let path = std::env::temp_dir().join("rust_ed_test_open_missing.txt");
+        let _ = std::fs::remove_file(&path);
+        let app = Application::open(&path, MockClipboard::new()).unwrap();
+        assert_eq!(app.editor.to_string(), "");
+        assert_eq!(app.file_path, Some(path.clone()));
+        assert!(!path.exists());
+
+        assert!(Application::open(std::env::temp_dir(), MockClipboard::new()).is_err());
+    }
+
+    #[test]
+    fn test_application_file_status() {
+        let mut app = Application::new(Editor::from("a"), MockClipboard::new());
+        assert_eq!(app.file_status(), "[No Name] [LF]");
+        app.editor.write('b');
+        assert_eq!(app.file_status(), "[No Name] [+] [LF]");
+
+        app.file_path = Some(PathBuf::from("notes.txt"));
+        app.editor.set_modified(false);
+        app.editor.line_ending = LineEnding::CrLf;
+        assert_eq!(app.file_status(), "notes.txt [CRLF]");
+    }
+
+    #[test]
+    fn test_application_quit_unsaved() {
+        let mut app = Application::new(Editor::from("a"), MockClipboard::new());
+        app.run_ex_command("q");
+        assert!(app.exit);
+
+        let mut app = Application::new(Editor::from("a"), MockClipboard::new());
+        app.editor.write('b');
+        app.run_ex_command("q");
+        assert!(!app.exit);
+        assert_eq!(app.log, "unsaved changes — press y to quit, n to cancel");
+        app.process_key_event(KeyEvent::Char('y'));
+        assert!(app.exit);
+
+        let mut app = Application::new(Editor::from("a"), MockClipboard::new());
+        app.editor.write('b');
+        app.run_ex_command("q!");
+        assert!(app.exit);
+
+        let path = std::env::temp_dir().join("rust_ed_test_quit_unsaved.txt");
+        let mut app = Application::new(Editor::from("a"), MockClipboard::new());
+        app.editor.write('b');
+        app.run_ex_command(&format!("w {}", path.display()));
+        assert!(!app.editor.is_modified());
+        app.run_ex_command("q");
+        assert!(app.exit);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_application_save_to_file() {
+        let path = std::env::temp_dir().join("rust_ed_test_save_to_file.txt");
+        std::fs::write(&path, "old").unwrap();
+        let mut app = Application::new(Editor::from("new"), MockClipboard::new());
+        app.save_to_file(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert!(!std::env::temp_dir()
+            .join(".rust_ed_test_save_to_file.txt.tmp")
+            .exists());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_application_save_through_symlink() {
+        let dir = std::env::temp_dir().join("rust_ed_test_save_symlink");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let real = dir.join("real.txt");
+        let link = dir.join("link.txt");
+        std::fs::write(&real, "old").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let mut app = Application::new(Editor::from("new"), MockClipboard::new());
+        app.save_to_file(&link).unwrap();
+        assert!(std::fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(std::fs::read_to_string(&real).unwrap(), "new");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_application_save_backup() {
+        let path = std::env::temp_dir().join("rust_ed_test_save_backup.txt");
+        let backup = std::env::temp_dir().join("rust_ed_test_save_backup.txt.bak");
+        let _ = std::fs::remove_file(&path);
+        let mut app = Application::new(Editor::from("one"), MockClipboard::new());
+        app.make_backup = true;
+        app.backup_suffix = ".bak".to_string();
+
+        app.save_to_file(&path).unwrap();
+        assert!(!backup.exists());
+
+        app.editor = Editor::from("two");
+        app.save_to_file(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "two");
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "one");
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(backup).unwrap();
+    }
+
+    #[test]
+    fn test_application_positions() {
+        let path = std::env::temp_dir().join("rust_ed_test_positions.txt");
+        let state = std::env::temp_dir().join("rust_ed_test_positions/positions");
+        let _ = std::fs::remove_file(&state);
+        std::fs::write(&path, "abc\ndef\nghi").unwrap();
+
+        let mut app = Application::open(&path, MockClipboard::new()).unwrap();
+        app.positions_file = Some(state.clone());
+        app.editor.set_cursor((2, 1));
+        app.save_positions();
+
+        let mut app = Application::open(&path, MockClipboard::new()).unwrap();
+        app.positions_file = Some(state.clone());
+        app.restore_position();
+        assert_eq!(app.editor.cursor_pos(), Vector2(2, 1));
+
+        // a path the state directory can not be made in is ignored
+        app.positions_file = Some(path.join("positions"));
+        app.save_positions();
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_dir_all(state.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_application_reload_file() {
+        let path = std::env::temp_dir().join("rust_ed_test_reload_file.txt");
+        std::fs::write(&path, "abc\ndef").unwrap();
+        let mut app = Application::open(&path, MockClipboard::new()).unwrap();
+        app.editor.set_cursor((2, 1));
+        app.editor.write('x');
+
+        app.run_ex_command("e");
+        assert_eq!(app.log, "unsaved changes — use :e! to discard them");
+        assert_eq!(app.editor.to_string(), "abc\ndexf");
+
+        std::fs::write(&path, "abc\nd").unwrap();
+        app.run_ex_command("e!");
+        assert_eq!(app.editor.to_string(), "abc\nd");
+        assert_eq!(app.editor.cursor_pos(), Vector2(1, 1));
+        assert!(!app.editor.is_modified());
+
+        std::fs::remove_file(&path).unwrap();
+        app.run_ex_command("e!");
+        assert!(app.log.starts_with(&format!("{}: ", path.display())));
+        assert_eq!(app.editor.to_string(), "abc\nd");
+    }
+
+    #[test]
+    fn test_application_changed_on_disk() {
+        let path = std::env::temp_dir().join("rust_ed_test_changed_on_disk.txt");
+        std::fs::write(&path, "a").unwrap();
+        let mut app = Application::open(&path, MockClipboard::new()).unwrap();
+        app.editor.write('b');
+
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH).unwrap();
+        app.run_ex_command("wq");
+        assert_eq!(app.log, "file changed on disk — overwrite? (y/n)");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a");
+        assert!(app.save_to_file(&path).is_err());
+
+        app.process_key_event(KeyEvent::Char('y'));
+        assert!(app.exit);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "ba");
+        assert!(!app.changed_on_disk(&path));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_application_open_file() {
+        let path = std::env::temp_dir().join("rust_ed_test_open_file.txt");
+        std::fs::write(&path, "one\ntwo").unwrap();
+        let mut app = Application::new(Editor::from("a"), MockClipboard::new());
+        app.editor.write('b');
+
+        app.render_opts.view.location = Vector2(0, 5);
+        app.run_ex_command(&format!("e {}", path.display()));
+        assert_eq!(app.editor.to_string(), "one\ntwo");
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 0));
+        assert_eq!(app.render_opts.view.location, Vector2(0, 0));
+        assert_eq!(app.file_path, Some(path.clone()));
+        std::fs::remove_file(&path).unwrap();
+
+        app.run_action(Action::Open, "rust_ed_test_open_missing_file.txt");
+        assert_eq!(app.editor.to_string(), "");
+        assert_eq!(
+            app.file_path,
+            Some(PathBuf::from("rust_ed_test_open_missing_file.txt"))
+        );
+    }
+
+    #[test]
+    fn test_application_buffers() {
+        let mut app = Application::new(Editor::from("a"), MockClipboard::new());
+        app.editor.write('b');
+        app.render_opts.view.location = Vector2(0, 3);
+        app.open_file(Path::new("rust_ed_test_buffer_one.txt"));
+        app.open_file(Path::new("rust_ed_test_buffer_two.txt"));
+        assert_eq!(app.file_status(), "[3/3] rust_ed_test_buffer_two.txt [LF]");
+
+        app.run_ex_command("bn");
+        assert_eq!(app.editor.to_string(), "ba");
+        assert_eq!(app.editor.cursor_pos(), Vector2(1, 0));
+        assert_eq!(app.render_opts.view.location, Vector2(0, 3));
+        assert!(app.editor.is_modified());
+
+        app.run_ex_command("bp");
+        assert_eq!(
+            app.file_path,
+            Some(PathBuf::from("rust_ed_test_buffer_two.txt"))
+        );
+        app.run_ex_command("b 2");
+        assert_eq!(
+            app.file_path,
+            Some(PathBuf::from("rust_ed_test_buffer_one.txt"))
+        );
+        app.run_ex_command("b 4");
+        assert_eq!(app.log, "no buffer 4");
+
+        app.open_file(Path::new("rust_ed_test_buffer_two.txt"));
+        assert_eq!(app.file_status(), "[3/3] rust_ed_test_buffer_two.txt [LF]");
+        app.run_ex_command("q");
+        assert!(!app.exit);
+    }
+
+    #[test]
+    fn test_application_highlighter() {
+        let mut app = Application::new(Editor::new(), MockClipboard::new());
+        assert!(app.highlighter.is_none());
+        app.open_file(Path::new("rust_ed_test_highlight.rs"));
+        assert!(app.highlighter.is_some());
+        app.open_file(Path::new("rust_ed_test_highlight.txt"));
+        assert!(app.highlighter.is_none());
+        app.run_ex_command("bp");
+        assert!(app.highlighter.is_some());
+    }
+
+    #[test]
+    fn test_application_scroll_half_page() {
+        let text = (0..10)
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut app = Application::new(Editor::from(text), MockClipboard::new());
+        app.render_opts.view.height = 6;
+        app.editor.set_cursor((0, 1));
+
+        app.scroll_half_page(true);
+        assert_eq!(app.render_opts.view.location, Vector2(0, 3));
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 4));
+        app.scroll_half_page(true);
+        app.scroll_half_page(true);
+        app.scroll_half_page(true);
+        assert_eq!(app.render_opts.view.location, Vector2(0, 9));
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 9));
+
+        app.scroll_half_page(false);
+        assert_eq!(app.render_opts.view.location, Vector2(0, 6));
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 6));
+        app.scroll_half_page(false);
+        app.scroll_half_page(false);
+        assert_eq!(app.render_opts.view.location, Vector2(0, 0));
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 0));
+    }
+
+    #[test]
+    fn test_application_cursor_shape() {
+        let mut app = Application::new(Editor::from("abc"), MockClipboard::new());
+        assert_eq!(app.cursor_shape(), CursorShape::BlinkingBar);
+        app.process_key_event(KeyEvent::Alt('z'));
+        assert_eq!(app.cursor_shape(), CursorShape::Block);
+        app.awaiting_view_align = false;
+        assert_eq!(app.cursor_shape(), CursorShape::BlinkingBar);
+
+        app.editor.begin_select();
+        assert_eq!(app.cursor_shape(), CursorShape::Block);
+        app.pending_count = Some(2);
+        app.editor.clear_selection();
+        assert_eq!(app.cursor_shape(), CursorShape::Block);
+    }
+
+    #[test]
+    fn test_application_expire_log() {
+        let mut app = Application::new(Editor::new(), MockClipboard::new());
+        let now = Instant::now();
+        app.log = "copied selection".to_string();
+        app.start_log_timer(now);
+        assert!(!app.expire_log(now + Duration::from_secs(1)));
+        assert!(app.expire_log(now + LOG_TIMEOUT));
+        assert_eq!(app.log, "");
+
+        app.error("save failed".to_string());
+        app.start_log_timer(now);
+        assert!(!app.expire_log(now + LOG_TIMEOUT));
+        assert!(app.expire_log(now + ERROR_LOG_TIMEOUT));
+
+        // a question stays until it is answered
+        app.log = "quit without saving? (y/n)".to_string();
+        app.awaiting_quit = true;
+        app.start_log_timer(now);
+        assert!(!app.expire_log(now + ERROR_LOG_TIMEOUT));
+        assert_eq!(app.log, "quit without saving? (y/n)");
+    }
+
+    #[test]
+    fn test_application_auto_save() {
+        let path = std::env::temp_dir().join("rust_ed_test_auto_save.txt");
+        std::fs::write(&path, "abc").unwrap();
+        let mut app = Application::open(&path, MockClipboard::new()).unwrap();
+        let now = Instant::now();
+        app.editor.write('x');
+        assert!(!app.auto_save_file(now + Duration::from_secs(60)));
+
+        app.auto_save = Some(Duration::from_secs(5));
+        assert!(!app.auto_save_file(now));
+        assert!(!app.auto_save_file(now + Duration::from_secs(4)));
+        assert!(app.auto_save_file(now + Duration::from_secs(5)));
+        assert_eq!(app.log, "auto-saved");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "xabc");
+        assert!(!app.auto_save_file(now + Duration::from_secs(20)));
+        std::fs::remove_file(&path).unwrap();
+
+        // a path the user has not opened or saved to is left alone, even when it exists
+        std::fs::write(&path, "abc").unwrap();
+        let mut app = Application::new(Editor::from("abc"), MockClipboard::new());
+        app.file_path = Some(path.clone());
+        app.auto_save = Some(Duration::from_secs(5));
+        app.editor.write('y');
+        assert!(!app.auto_save_file(now));
+        assert!(!app.auto_save_file(now + Duration::from_secs(60)));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "abc");
+
+        assert!(app.write_file(None));
+        app.editor.write('z');
+        assert!(!app.auto_save_file(now + Duration::from_secs(60)));
+        assert!(app.auto_save_file(now + Duration::from_secs(65)));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "yzabc");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_application_resize() {
+        let text = (0..20)
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut app = Application::new(Editor::from(text), MockClipboard::new());
+        app.scroll_off = 0;
+        assert!(app.resize(20, 10));
+        assert!(!app.resize(20, 10));
+        app.editor.set_cursor((0, 9));
+        app.last_frame = vec![String::new(); 10];
+
+        assert!(app.resize(20, 5));
+        assert!(app.last_frame.is_empty());
+        assert_eq!(app.render_opts.view.location, Vector2(0, 5));
+    }
+
+    #[test]
+    fn test_application_read_only() {
+        let mut app = Application::new(Editor::from("abc def"), MockClipboard::new());
+        app.run_ex_command("view");
+        assert!(app.read_only);
+        assert_eq!(app.cursor_shape(), CursorShape::Block);
+        assert!(app.file_status().contains("[RO]"));
+
+        app.apply_text_object("diw", None);
+        app.apply_line_operator('d', 1, None);
+        app.run_ex_command("s/abc/x/");
+        assert_eq!(app.log, "buffer is read-only");
+        assert_eq!(app.editor.to_string(), "abc def");
+
+        app.apply_line_operator('y', 1, None);
+        assert_eq!(app.log, "yanked 1 lines");
+    }
+
+    #[test]
+    fn test_application_keymap() {
+        let mut app = Application::new(Editor::from("abc"), MockClipboard::new());
+        let (config, _) = Config::parse("keys.^q = \"align_view\"\nkeys.M-z = \"none\"\n");
+        app.apply_config(&config);
+        app.process_key_event(KeyEvent::Ctrl('q'));
+        assert!(app.awaiting_view_align);
+        assert_eq!(app.keymap.get(KeyEvent::Alt('z')), None);
+    }
+
+    #[test]
+    fn test_application_align_view() {
+        let text = (0..20)
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut app = Application::new(Editor::from(text), MockClipboard::new());
+        app.render_opts.view.height = 8;
+        app.editor.set_cursor((0, 10));
+
+        app.align_view('t');
+        assert_eq!(app.render_opts.view.location, Vector2(0, 7));
+        app.align_view('b');
+        assert_eq!(app.render_opts.view.location, Vector2(0, 6));
+        app.align_view('z');
+        assert_eq!(app.render_opts.view.location, Vector2(0, 6));
+
+        app.editor.set_cursor((0, 1));
+        app.align_view('t');
+        assert_eq!(app.render_opts.view.location, Vector2(0, 0));
+    }
+
+    #[test]
+    fn test_application_scroll_to_cursor() {
+        let text = (0..20)
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut app = Application::new(Editor::from(text), MockClipboard::new());
+        app.render_opts.view.height = 10;
+        app.render_opts.view.width = 10;
+
+        app.editor.set_cursor((0, 6));
+        assert!(!app.scroll_to_cursor());
+        app.editor.set_cursor((0, 7));
+        assert!(app.scroll_to_cursor());
+        assert_eq!(app.render_opts.view.location, Vector2(0, 1));
+
+        app.editor.set_cursor((0, 3));
+        assert!(app.scroll_to_cursor());
+        assert_eq!(app.render_opts.view.location, Vector2(0, 0));
+
+        app.scroll_off = 0;
+        app.editor.set_cursor((0, 9));
+        assert!(!app.scroll_to_cursor());
+    }
+
+    #[test]
+    fn test_application_scroll_to_cursor_horizontal() {
+        let mut app = Application::new(Editor::from("a".repeat(40)), MockClipboard::new());
+        app.render_opts.view.height = 1;
+        app.render_opts.view.width = 20;
+        app.side_scroll_off = 2;
+
+        app.editor.set_cursor((17, 0));
+        assert!(!app.scroll_to_cursor());
+        app.editor.set_cursor((30, 0));
+        assert!(app.scroll_to_cursor());
+        assert_eq!(app.render_opts.view.location, Vector2(13, 0));
+
+        app.editor.set_cursor((14, 0));
+        assert!(app.scroll_to_cursor());
+        assert_eq!(app.render_opts.view.location, Vector2(12, 0));
+        app.editor.set_cursor((0, 0));
+        app.scroll_to_cursor();
+        assert_eq!(app.render_opts.view.location, Vector2(0, 0));
+    }
+
+    #[test]
+    fn test_application_scroll_to_cursor_wrap() {
+        let mut app = Application::new(
+            Editor::from(format!("{}\nb\nc", "a".repeat(40))),
+            MockClipboard::new(),
+        );
+        app.render_opts.view.height = 3;
+        app.render_opts.view.width = 10;
+        app.scroll_off = 0;
+        app.toggle_wrap();
+
+        app.editor.set_cursor((30, 0));
+        assert!(!app.scroll_to_cursor());
+        app.editor.set_cursor((0, 1));
+        assert!(app.scroll_to_cursor());
+        assert_eq!(app.render_opts.view.location, Vector2(0, 1));
+        assert_eq!(app.screen_to_buffer(0, 1), Vector2(0, 2));
+
+        app.render_opts.view.location = Vector2(0, 0);
+        assert_eq!(app.screen_to_buffer(3, 2), Vector2(23, 0));
+    }
+
+    #[test]
+    fn test_application_scroll_view() {
+        let mut app = Application::new(Editor::from("a\nb\nc"), MockClipboard::new());
+        app.render_opts.view.height = 2;
+        app.scroll_view(MOUSE_SCROLL_LINES);
+        assert_eq!(app.render_opts.view.location, Vector2(0, 3));
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 0));
+
+        app.scroll_view(-10);
+        assert_eq!(app.render_opts.view.location, Vector2(0, -2));
+    }
+
+    #[test]
+    fn test_application_screen_to_buffer() {
+        let mut app = Application::new(Editor::from("a\n\tbc\nd"), MockClipboard::new());
+        app.render_opts.line_numbers = LineNumberMode::Absolute;
+        app.render_opts.tab_width = 4;
+        app.render_opts.view.location = Vector2(0, 1);
+        assert_eq!(app.screen_to_buffer(7, 0), Vector2(2, 1));
+        assert_eq!(app.screen_to_buffer(3, 0), Vector2(0, 1));
+        assert_eq!(app.screen_to_buffer(2, 1), Vector2(0, 2));
+    }
+
+    #[test]
+    fn test_application_mouse_drag() {
+        let mut app = Application::new(Editor::from("abc\ndef"), MockClipboard::new());
+        app.mouse_press(Vector2(1, 0));
+        assert!(!app.editor.is_selecting());
+
+        app.mouse_drag(Vector2(2, 1));
+        app.process_mouse_event(MouseEvent::Release(0, 0));
+        assert_eq!(app.editor.cursor_pos(), Vector2(2, 1));
+        assert_eq!(
+            app.editor.selection_range(),
+            Some((Vector2(1, 0), Vector2(2, 1)))
+        );
+
+        app.mouse_drag(Vector2(0, 0));
+        assert_eq!(app.editor.cursor_pos(), Vector2(2, 1));
+    }
+
+    #[test]
+    fn test_application_theme() {
+        let mut app = Application::new(Editor::new(), MockClipboard::new());
+        app.run_ex_command("theme light");
+        assert_eq!(app.render_opts.theme, Theme::light());
+        app.run_ex_command("theme blue");
+        assert_eq!(app.log, "usage: theme dark|light");
+        assert_eq!(app.render_opts.theme, Theme::light());
+        app.run_ex_command("theme dark");
+        assert_eq!(app.render_opts.theme, Theme::dark());
+    }
+
+    #[test]
+    fn test_application_text_object() {
+        let mut app = Application::new(Editor::from("one two three"), MockClipboard::new());
+        app.editor.set_cursor((5, 0));
+        app.apply_text_object("diw", None);
+        assert_eq!(app.editor.to_string(), "one  three");
+        assert_eq!(app.editor.cursor_pos(), Vector2(4, 0));
+        assert_eq!(app.registers[&UNNAMED_REGISTER].text, "two");
+
+        app.editor.set_cursor((0, 0));
+        app.apply_text_object("yaw", Some('a'));
+        assert_eq!(app.registers[&'a'].text, "one  ");
+        app.apply_text_object("daw", None);
+        assert_eq!(app.editor.to_string(), "three");
+
+        app.apply_text_object("viw", None);
+        assert_eq!(
+            app.editor.selection_range(),
+            Some((Vector2(0, 0), Vector2(5, 0)))
+        );
+
+        app.editor = Editor::from("f(a, \"b\")");
+        app.editor.set_cursor((6, 0));
+        app.apply_text_object("ci\"", None);
+        assert_eq!(app.editor.to_string(), "f(a, \"\")");
+        app.apply_text_object("da)", None);
+        assert_eq!(app.editor.to_string(), "f");
+
+        app.apply_text_object("dxw", None);
+        assert_eq!(app.log, "text objects are i or a followed by an object");
+        app.apply_text_object("diq", None);
+        assert_eq!(app.log, "unknown text object: q");
+    }
+
+    #[test]
+    fn test_application_macros() {
+        use KeyEvent::*;
+
+        let mut app = Application::new(Editor::from("a\nb\nc\nd\n"), MockClipboard::new());
+        for key in vec![Alt('Q'), Char('q'), Char('-'), Left, Down, Alt('Q')] {
+            app.process_key_event(key);
+        }
+        assert_eq!(app.log, "recorded 3 keys into q");
+        assert_eq!(app.editor.to_string(), "-a\nb\nc\nd\n");
+
+        app.process_key_event(Alt('@'));
+        app.process_key_event(Char('q'));
+        assert_eq!(app.editor.to_string(), "-a\n-b\nc\nd\n");
+
+        // only the keys that replayed q are recorded, not the keys the replay processed
+        for key in vec![Alt('Q'), Char('w'), Alt('@'), Char('q'), Alt('Q')] {
+            app.process_key_event(key);
+        }
+        assert_eq!(app.log, "recorded 2 keys into w");
+        assert_eq!(app.macros[&'w'], vec![Alt('@'), Char('q')]);
+        assert_eq!(app.editor.to_string(), "-a\n-b\n-c\nd\n");
+
+        // replaying stops when the cursor cannot move down any further
+        assert_eq!(app.replay_macro('q', 10), 1);
+        assert_eq!(app.editor.to_string(), "-a\n-b\n-c\n-d\n-");
+        assert_eq!(app.last_macro, Some('q'));
+
+        assert_eq!(app.replay_macro('x', 1), 0);
+        assert_eq!(app.log, "nothing recorded into x");
+    }
+
+    #[test]
+    fn test_application_jump_list() {
+        let text = (0..10)
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut app = Application::new(Editor::from(text), MockClipboard::new());
+        app.run_ex_command("5");
+        app.run_ex_command("9");
+        app.run_ex_command("9");
+        assert_eq!(
+            app.jump_list,
+            vec![Vector2(0, 0), Vector2(0, 4), Vector2(0, 8)]
+        );
+
+        assert!(app.jump_back());
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 4));
+        assert!(app.jump_back());
+        assert!(!app.jump_back());
+        assert_eq!(app.log, "at the oldest jump");
+        assert!(app.jump_forward());
+        assert!(app.jump_forward());
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 8));
+        assert!(!app.jump_forward());
+
+        // a new jump drops the locations after the one returned to
+        app.jump_back();
+        app.jump_back();
+        app.run_ex_command("3");
+        assert_eq!(app.jump_list, vec![Vector2(0, 0)]);
+        assert!(app.jump_back());
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 0));
+        assert!(app.jump_forward());
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 2));
+    }
+
+    #[test]
+    fn test_application_marks() {
+        let mut app = Application::new(Editor::from("a\nb\nc"), MockClipboard::new());
+        app.editor.set_cursor((0, 2));
+        app.set_mark('x');
+        app.editor.set_cursor((0, 0));
+        app.editor.write_at((0, 0), '\n');
+
+        assert!(app.jump_to_mark('x'));
+        assert_eq!(app.editor.cursor_pos(), Vector2(0, 3));
+        assert!(!app.jump_to_mark('y'));
+        assert_eq!(app.log, "mark y is not set");
+        app.set_mark('A');
+        assert_eq!(app.log, "marks are named a to z");
+    }
+
+    #[test]
+    fn test_application_comment_prefix() {
+        let mut app = Application::new(Editor::new(), MockClipboard::new());
+        assert_eq!(app.comment_prefix(), "// ");
+        app.file_path = Some(PathBuf::from("script.py"));
+        assert_eq!(app.comment_prefix(), "# ");
+        app.comment_prefixes
+            .insert("py".to_string(), "#".to_string());
+        assert_eq!(app.comment_prefix(), "#");
+        app.file_path = Some(PathBuf::from("main.rs"));
+        assert_eq!(app.comment_prefix(), "// ");
+    }
+
+    #[test]
+    fn test_application_delete_lines() {
+        let mut app = Application::new(Editor::from("a\nb\n  c\nd"), MockClipboard::new());
+        app.apply_line_operator('d', 2, None);
+        assert_eq!(app.editor.to_string(), "  c\nd");
+        assert_eq!(app.editor.cursor_pos(), Vector2(2, 0));
+        assert_eq!(app.log, "deleted 2 lines");
+        assert_eq!(app.registers[&UNNAMED_REGISTER].text, "a\nb\n");
+        assert!(app.registers[&UNNAMED_REGISTER].linewise);
+
+        // a count past the end deletes the lines that are left
+        app.editor.set_cursor((0, 1));
+        app.apply_line_operator('d', 5, Some('a'));
+        assert_eq!(app.editor.to_string(), "  c");
+        assert_eq!(app.registers[&'a'].text, "d\n");
+        app.apply_line_operator('d', 1, None);
+        assert_eq!(app.editor.to_string(), "");
+        app.apply_line_operator('d', 1, None);
+    }
+
+    #[test]
+    fn test_application_yank_lines() {
+        let mut app = Application::new(Editor::from("a\nb\nc"), MockClipboard::new());
+        app.editor.set_cursor((1, 1));
+        app.apply_line_operator('y', 2, None);
+        assert_eq!(app.log, "yanked 2 lines");
+        assert_eq!(app.editor.to_string(), "a\nb\nc");
+        assert_eq!(app.editor.cursor_pos(), Vector2(1, 1));
+        assert_eq!(app.clipboard.paste().unwrap(), "b\nc\n");
+
+        // linewise copies are pasted below the cursor's line
+        app.editor.set_cursor((0, 0));
+        app.paste(None, 1);
+        assert_eq!(app.editor.to_string(), "a\nb\nc\nb\nc");
+    }
+
+    #[test]
+    fn test_application_cut_selection() {
+        let mut app = Application::new(Editor::from("one\ntwo\nthree"), MockClipboard::new());
+        app.editor.set_cursor((1, 0));
+        app.editor.begin_select();
+        app.editor.set_cursor((1, 1));
+        app.cut_selection(None);
+        assert_eq!(app.editor.to_string(), "owo\nthree");
+        assert_eq!(app.registers[&UNNAMED_REGISTER].text, "ne\nt");
+        assert!(!app.editor.is_selecting());
+
+        app.editor.begin_line_select();
+        app.cut_selection(Some('a'));
+        assert_eq!(app.editor.to_string(), "three");
+        assert!(app.registers[&'a'].linewise);
+
+        app.cut_selection(None);
+        assert_eq!(app.log, "nothing selected");
+    }
+
+    #[test]
+    fn test_application_tab_text() {
+        let mut app = Application::new(Editor::from("ab\n"), MockClipboard::new());
+        app.render_opts.tab_width = 4;
+        app.editor.set_cursor((2, 0));
+        assert_eq!(app.tab_text(), "  ");
+
+        app.run_ex_command("hardtabs");
+        assert_eq!(app.log, "hard tabs on");
+        assert_eq!(app.tab_text(), "\t");
+    }
+
+    #[test]
+    fn test_application_auto_close() {
+        let mut app = Application::new(Editor::new(), MockClipboard::new());
+        for x in "f(a[\"b".chars() {
+            app.type_char(x);
+        }
+        assert_eq!(app.editor.to_string(), "f(a[\"b\"])");
+        for x in "\"]) don't".chars() {
+            app.type_char(x);
+        }
+        assert_eq!(app.editor.to_string(), "f(a[\"b\"]) don't");
+
+        app.run_ex_command("autoclose");
+        assert_eq!(app.log, "auto close off");
+        app.type_char('(');
+        assert_eq!(app.editor.to_string(), "f(a[\"b\"]) don't(");
+    }
+
+    #[test]
+    fn test_application_surround() {
+        let mut app = Application::new(Editor::from("a word\nb"), MockClipboard::new());
+        app.apply_text_object("vaw", None);
+        app.surround_selection('(');
+        assert_eq!(app.editor.to_string(), "(a )word\nb");
+
+        app.editor.begin_line_select();
+        app.editor.set_cursor((0, 1));
+        app.surround_selection('\'');
+        assert_eq!(app.editor.to_string(), "'(a )word\nb'");
+
+        app.surround_selection('"');
+        assert_eq!(app.log, "nothing selected");
+        app.editor.begin_select();
+        app.surround_selection('x');
+        assert_eq!(app.log, "cannot surround with x");
+    }
+
+    #[test]
+    fn test_application_cursor_status() {
+        let mut app = Application::new(Editor::from("a\nb\nc\nd"), MockClipboard::new());
+        assert_eq!(app.cursor_status(), "Ln 1, Col 1  25%");
+        app.editor.set_cursor((1, 3));
+        assert_eq!(app.cursor_status(), "Ln 4, Col 2 100%");
+
+        assert_eq!(fit_status("file", "Ln 1", 10), "file  Ln 1");
+        assert_eq!(fit_status("a long name", "Ln 1", 10), "a lon Ln 1");
+        assert_eq!(fit_status("file", "Ln 1", 3), "fil");
+    }
+
+    #[test]
+    fn test_application_select_all() {
+        let mut app = Application::new(Editor::from("a\nbc"), MockClipboard::new());
+        app.editor.set_cursor((1, 0));
+        app.run_ex_command("%");
+        assert_eq!(
+            app.editor.selection_range(),
+            Some((Vector2(0, 0), Vector2(2, 1)))
+        );
+        assert_eq!(app.editor.selection_kind(), SelectionKind::Line);
+
+        app.copy_selection(None);
+        assert_eq!(app.clipboard.paste().unwrap(), "a\nbc\n");
+        assert!(app.registers[&UNNAMED_REGISTER].linewise);
+    }
+
+    #[test]
+    fn test_application_stats() {
+        let mut app = Application::new(Editor::from("one two\nthree"), MockClipboard::new());
+        app.editor.set_cursor((2, 1));
+        app.run_ex_command("stats");
+        assert_eq!(
+            app.log,
+            "2 lines, 3 words, 13 characters; line 2 of 2, column 3"
+        );
+
+        app.editor.set_cursor((4, 0));
+        app.editor.begin_select();
+        app.editor.set_cursor((1, 1));
+        assert_eq!(
+            app.stats(),
+            "selected: 2 lines, 2 words, 5 characters; line 2 of 2, column 2"
+        );
+
+        // characters are counted rather than the bytes they are encoded in
+        let mut app = Application::new(Editor::from("café"), MockClipboard::new());
+        assert!(app.stats().starts_with("1 lines, 1 words, 4 characters"));
+        app.editor.set_cursor((3, 0));
+        app.editor.begin_select();
+        app.editor.set_cursor((4, 0));
+        assert!(app
+            .stats()
+            .starts_with("selected: 1 lines, 1 words, 1 characters"));
+    }
+
+    #[test]
+    fn test_application_color_column() {
+        let mut app = Application::new(Editor::new(), MockClipboard::new());
+        app.run_ex_command("colorcolumn 80");
+        assert_eq!(app.render_opts.color_column, Some(80));
+        app.run_ex_command("colorcolumn x");
+        assert_eq!(app.log, "usage: colorcolumn [column]");
+        assert_eq!(app.render_opts.color_column, Some(80));
+        app.run_ex_command("colorcolumn");
+        assert_eq!(app.render_opts.color_column, None);
+    }
+
+    #[test]
+    fn test_application_save_failed() {
+        let mut app = Application::new(Editor::from("a"), MockClipboard::new());
+        app.editor.write('b');
+        let path = std::env::temp_dir()
+            .join("rust_ed_missing_dir")
+            .join("a.txt");
+        assert!(!app.write_file(Some(&path)));
+        assert!(app.log.starts_with("save failed: "));
+        assert!(app.editor.is_modified());
+        assert_eq!(app.file_path, None);
+    }
+
+    #[test]
+    fn test_application_prompt_history() {
+        let mut app = Application::new(Editor::new(), MockClipboard::new());
+        app.search_history = vec!["a".to_string(), "b".to_string()];
+        app.replace_history = vec!["x/y".to_string()];
+
+        app.prompt = Some(Prompt::new(Action::Search {
+            regex: false,
+            reverse: false,
+        }));
+        app.prompt.as_mut().unwrap().text = "typed".to_string();
+        let text = |app: &Application<MockClipboard>| app.prompt.as_ref().unwrap().text.clone();
+
+        app.recall_history(true);
+        assert_eq!(text(&app), "b");
+        app.recall_history(true);
+        app.recall_history(true);
+        assert_eq!(text(&app), "a");
+        app.recall_history(false);
+        assert_eq!(text(&app), "b");
+        app.recall_history(false);
+        assert_eq!(text(&app), "typed");
+
+        // each kind of prompt has its own history
+        app.prompt = Some(Prompt::new(Action::Replace));
+        app.recall_history(true);
+        assert_eq!(text(&app), "x/y");
+    }
+
+    #[test]
+    fn test_application_confirm_replace() {
+        let mut app = Application::new(Editor::from("aaa\na\na"), MockClipboard::new());
+        app.run_action(Action::Replace, "a/bb/gc");
+        assert!(app.editor.is_selected((0, 0)));
+
+        app.answer_replace('y');
+        app.answer_replace('n');
+        assert!(app.editor.is_selected((3, 0)));
+        app.answer_replace('y');
+        app.answer_replace('q');
+        assert_eq!(app.editor.to_string(), "bbabb\na\na");
+        assert_eq!(app.log, "replaced 2 occurrences");
+        assert!(!app.editor.is_selecting());
+
+        app.run_action(Action::Replace, "a/c/c");
+        app.answer_replace('n');
+        app.answer_replace('a');
+        assert_eq!(app.editor.to_string(), "bbabb\nc\nc");
+        assert_eq!(app.log, "replaced 2 occurrences");
+    }
+
+    #[test]
+    fn test_application_paste_seeded_clipboard() {
+        let mut app = Application::new(Editor::new(), MockClipboard::with_contents("a\r\nb"));
+
+        // carriage returns are dropped when pasting
+        app.paste(None, 1);
+        assert_eq!(app.editor.to_string(), "a\nb");
     }
 }
@@ -2,6 +2,7 @@
 //! temporary text buffer
 use clipboard::{ClipboardContext, ClipboardProvider};
 use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::fmt;
 
 #[derive(Debug)]
@@ -76,6 +77,43 @@ impl Clipboard for MemoryClipboard {
     }
 }
 
+/// Clipboard for tests which stores the copied text behind a `RefCell`
+///
+/// # Errors
+/// None of the mock clipboard operations will return an error
+///
+#[derive(Default)]
+pub struct MockClipboard {
+    contents: RefCell<String>,
+}
+
+impl MockClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// create a mock clipboard which already holds `contents`
+    pub fn with_contents(contents: impl Into<String>) -> Self {
+        Self {
+            contents: RefCell::new(contents.into()),
+        }
+    }
+}
+
+impl Clipboard for MockClipboard {
+    fn paste(&self) -> Result<String> {
+        Ok(self.contents.borrow().clone())
+    }
+
+    fn copy<T>(&mut self, content: T) -> Result<()>
+    where
+        T: Into<String>,
+    {
+        self.contents.replace(content.into());
+        Ok(())
+    }
+}
+
 /// Use the operating systems clipboard to copy and paste data
 ///
 /// # Errors
@@ -113,6 +151,62 @@ impl Clipboard for OsClipboard {
     }
 }
 
+/// the number of copies remembered by a `ClipboardHistory`
+pub const HISTORY_LENGTH: usize = 16;
+
+/// Wraps a clipboard and remembers the most recent copies made through it
+///
+/// # Errors
+/// Errors from the wrapped clipboard are returned unchanged
+///
+pub struct ClipboardHistory<T>
+where
+    T: Clipboard,
+{
+    pub inner: T,
+    history: VecDeque<String>,
+}
+
+impl<T> ClipboardHistory<T>
+where
+    T: Clipboard,
+{
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            history: VecDeque::with_capacity(HISTORY_LENGTH),
+        }
+    }
+
+    /// return the nth most recent copy, where zero is the latest
+    pub fn paste_nth(&self, n: usize) -> Option<String> {
+        self.history.get(n).cloned()
+    }
+}
+
+impl<T> Clipboard for ClipboardHistory<T>
+where
+    T: Clipboard,
+{
+    fn paste(&self) -> Result<String> {
+        self.inner.paste()
+    }
+
+    /// Copy `content` into the wrapped clipboard and the history.
+    /// copying the same content twice in a row only records it once
+    fn copy<U>(&mut self, content: U) -> Result<()>
+    where
+        U: Into<String>,
+    {
+        let content = content.into();
+        if self.history.front() != Some(&content) {
+            self.history.push_front(content.clone());
+            self.history.truncate(HISTORY_LENGTH);
+        }
+        self.inner.copy(content)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -127,6 +221,38 @@ mod test {
         assert_eq!(TEST_DATA, clipboard.paste().unwrap());
     }
 
+    #[test]
+    fn test_mock_clipboard() {
+        let mut clipboard = MockClipboard::with_contents("seeded");
+        assert_eq!(clipboard.paste().unwrap(), "seeded");
+
+        clipboard.copy(TEST_DATA).unwrap();
+        assert_eq!(clipboard.paste().unwrap(), TEST_DATA);
+    }
+
+    #[test]
+    fn test_clipboard_history() {
+        let mut clipboard = ClipboardHistory::new(MemoryClipboard::new());
+
+        for i in 0..HISTORY_LENGTH + 4 {
+            clipboard.copy(i.to_string()).unwrap();
+            clipboard.copy(i.to_string()).unwrap();
+        }
+
+        let latest = (HISTORY_LENGTH + 3).to_string();
+        assert_eq!(clipboard.paste().unwrap(), latest);
+        assert_eq!(clipboard.paste_nth(0), Some(latest));
+        assert_eq!(
+            clipboard.paste_nth(1),
+            Some((HISTORY_LENGTH + 2).to_string())
+        );
+        assert_eq!(
+            clipboard.paste_nth(HISTORY_LENGTH - 1),
+            Some("4".to_string())
+        );
+        assert_eq!(clipboard.paste_nth(HISTORY_LENGTH), None);
+    }
+
     #[test]
     fn test_os_clipboard() -> std::result::Result<(), Box<dyn std::error::Error>> {
         let mut clipboard = OsClipboard::new()?;
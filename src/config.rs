@@ -0,0 +1,200 @@
+//! settings read from `~/.config/rust-ed/config.toml` when the editor starts.
+//! the file is a list of toml style `key = value` lines, with `#` starting a comment.
+//! keys are rebound with lines like `keys.M-s = "sort_lines"`, or `"none"` to unbind them
+use crate::keymap::{self, Command};
+use crate::renderer::{LineNumberMode, Theme};
+use crossterm::input::KeyEvent;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The settings that can be changed in the config file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    /// the number of columns a tab takes up and an indent level is wide
+    pub tab_width: usize,
+    /// the lines kept between the cursor and the top and bottom of the screen
+    pub scroll_off: i32,
+    /// searches ignore the case of letters unless they contain uppercase ones
+    pub search_ignore_case: bool,
+    /// sorting lines compares letters regardless of case
+    pub sort_ignore_case: bool,
+    pub theme: Theme,
+    pub line_numbers: LineNumberMode,
+    /// save the buffer after this long without an edit, set in seconds with 0 turning it off
+    pub auto_save: Option<Duration>,
+    /// keys bound to a different command than their default, or to none
+    pub keys: Vec<(KeyEvent, Option<Command>)>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tab_width: 4,
+            scroll_off: 3,
+            search_ignore_case: true,
+            sort_ignore_case: true,
+            theme: Theme::default(),
+            line_numbers: LineNumberMode::None,
+            auto_save: None,
+            keys: Vec::new(),
+        }
+    }
+}
+
+/// return the path of the config file, inside `$XDG_CONFIG_HOME` or `~/.config`
+pub fn default_path() -> Option<PathBuf> {
+    let dir = match std::env::var_os("XDG_CONFIG_HOME").filter(|x| !x.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(dir.join("rust-ed").join("config.toml"))
+}
+
+impl Config {
+    /// read the config file at `path`, a missing file leaves every setting at its default
+    ///
+    /// # Returns
+    /// the config and a warning for each line that could not be used
+    pub fn load(path: &Path) -> (Config, Vec<String>) {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Config::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (Config::default(), Vec::new()),
+            Err(e) => (
+                Config::default(),
+                vec![format!("could not read {}: {}", path.display(), e)],
+            ),
+        }
+    }
+
+    /// parse the text of a config file, skipping the lines that can not be used
+    ///
+    /// # Returns
+    /// the config and a warning for each skipped line
+    pub fn parse(text: &str) -> (Config, Vec<String>) {
+        let mut config = Config::default();
+        let mut warnings = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), unquote(value.trim())),
+                None => {
+                    warnings.push(format!("config line {}: expected key = value", i + 1));
+                    continue;
+                }
+            };
+            if let Err(e) = config.set(key, value) {
+                warnings.push(format!("config line {}: {}", i + 1, e));
+            }
+        }
+        (config, warnings)
+    }
+
+    /// change the setting `key` to `value`
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("invalid value `{}` for {}", value, key);
+        if let Some(name) = key.strip_prefix("keys.") {
+            let key = keymap::parse_key(unquote(name)).ok_or(format!("unknown key {}", name))?;
+            let command = match value {
+                "none" => None,
+                _ => Some(Command::named(value).ok_or(format!("unknown command {}", value))?),
+            };
+            self.keys.push((key, command));
+            return Ok(());
+        }
+        match key {
+            "tab_width" => self.tab_width = value.parse().map_err(|_| invalid())?,
+            "scroll_off" => self.scroll_off = value.parse().map_err(|_| invalid())?,
+            "search_ignore_case" => {
+                self.search_ignore_case = value.parse().map_err(|_| invalid())?
+            }
+            "sort_ignore_case" => self.sort_ignore_case = value.parse().map_err(|_| invalid())?,
+            "auto_save" => {
+                let seconds: u64 = value.parse().map_err(|_| invalid())?;
+                self.auto_save = Some(Duration::from_secs(seconds)).filter(|x| !x.is_zero());
+            }
+            "theme" => self.theme = Theme::named(value).ok_or_else(invalid)?,
+            "line_numbers" => {
+                self.line_numbers = match value {
+                    "none" => LineNumberMode::None,
+                    "absolute" => LineNumberMode::Absolute,
+                    "relative" => LineNumberMode::Relative,
+                    "hybrid" => LineNumberMode::Hybrid,
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => return Err(format!("unknown setting {}", key)),
+        }
+        Ok(())
+    }
+}
+
+/// remove a `#` comment from the end of `line`, leaving any inside quotes
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// remove the quotes around a string value
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|x| x.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_config_parse() {
+        let (config, warnings) = Config::parse(
+            "# settings\n\
+             tab_width = 2\n\
+             theme = \"light\" # or dark\n\
+             line_numbers = \"hybrid\"\n\
+             search_ignore_case = false\n\
+             sort_ignore_case = false\n\
+             auto_save = 30\n\
+             scroll_off = many\n\
+             colour = \"red\"\n\
+             oops\n\
+             keys.M-s = \"reflow\"\n\
+             keys.\"^q\" = \"none\"\n\
+             keys.M-y = \"dance\"\n",
+        );
+        assert_eq!(config.tab_width, 2);
+        assert_eq!(config.theme, Theme::light());
+        assert_eq!(config.line_numbers, LineNumberMode::Hybrid);
+        assert!(!config.search_ignore_case);
+        assert!(!config.sort_ignore_case);
+        assert_eq!(config.auto_save, Some(Duration::from_secs(30)));
+        assert_eq!(config.scroll_off, Config::default().scroll_off);
+        assert_eq!(
+            warnings,
+            vec![
+                "config line 8: invalid value `many` for scroll_off",
+                "config line 9: unknown setting colour",
+                "config line 10: expected key = value",
+                "config line 13: unknown command dance",
+            ]
+        );
+        assert_eq!(
+            config.keys,
+            vec![
+                (KeyEvent::Alt('s'), Some(Command::Reflow)),
+                (KeyEvent::Ctrl('q'), None),
+            ]
+        );
+    }
+}
@@ -3,7 +3,10 @@
 
 // TODO: Make the write function erase the current selection before beginning a write
 
-use std::collections::VecDeque;
+use crate::pattern::{fold_case, Pattern};
+use std::cmp::min;
+use std::collections::{HashMap, VecDeque};
+use std::ops::{Range, RangeInclusive};
 
 /// Information for a particular character cell.
 /// Contains color values and other metadata
@@ -36,13 +39,111 @@ impl From<char> for CharCel {
     }
 }
 
+/// return the number of words in `cells`, runs of characters between whitespace
+pub fn count_words(cells: &[CharCel]) -> usize {
+    let mut count = 0;
+    let mut in_word = false;
+    for cell in cells {
+        let is_word = !cell.char.is_whitespace();
+        if is_word && !in_word {
+            count += 1;
+        }
+        in_word = is_word;
+    }
+    count
+}
+
+/// the kinds of characters that words are made of, a word is a run of characters of one kind
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CharClass {
+    Whitespace,
+    /// letters, digits and underscores
+    Word,
+    /// any other character
+    Punctuation,
+}
+
+impl CharClass {
+    pub fn of(c: char) -> Self {
+        if c.is_whitespace() {
+            CharClass::Whitespace
+        } else if c.is_alphanumeric() || c == '_' {
+            CharClass::Word
+        } else {
+            CharClass::Punctuation
+        }
+    }
+}
+
+/// return true for characters drawn as part of the character before them, such as combining
+/// accents, variation selectors, emoji skin tones and the zero width joiner
+fn extends_cluster(c: char) -> bool {
+    matches!(c as u32,
+        0x0300..=0x036F
+        | 0x1AB0..=0x1AFF
+        | 0x1DC0..=0x1DFF
+        | 0x200D
+        | 0x20D0..=0x20FF
+        | 0xFE00..=0xFE0F
+        | 0xFE20..=0xFE2F
+        | 0x1F3FB..=0x1F3FF
+        | 0xE0020..=0xE007F)
+}
+
+/// return true for the regional indicators, pairs of which are drawn as a flag
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+/// swap the case of a character, leaving it unchanged if its other case is several characters
+fn toggle_case(c: char) -> char {
+    let swapped: Vec<char> = if c.is_uppercase() {
+        c.to_lowercase().collect()
+    } else {
+        c.to_uppercase().collect()
+    };
+    match swapped[..] {
+        [x] => x,
+        _ => c,
+    }
+}
+
+/// how the case of letters is changed
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Case {
+    /// uppercase letters become lowercase and lowercase letters become uppercase
+    Toggle,
+    Lower,
+    Upper,
+}
+
+impl Case {
+    /// return `c` changed to this case, letters whose other case is several characters are kept
+    pub fn apply(self, c: char) -> char {
+        let changed: Vec<char> = match self {
+            Case::Toggle => return toggle_case(c),
+            Case::Lower => c.to_lowercase().collect(),
+            Case::Upper => c.to_uppercase().collect(),
+        };
+        match changed[..] {
+            [x] => x,
+            _ => c,
+        }
+    }
+}
+
 // TODO: create a trait for operations on a grid
 type Grid = Vec<Vec<CharCel>>;
 
 /// Very simple vector implementation
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub struct Vector2(pub i32, pub i32);
 impl Vector2 {
+    /// the vector (0, 0)
+    pub fn zero() -> Self {
+        Self(0, 0)
+    }
+
     /// Add two vectors together
     pub fn add(&self, a: impl Into<Self>) -> Self {
         let a = a.into();
@@ -54,6 +155,67 @@ impl Vector2 {
     pub fn y(&self) -> i32 {
         self.1
     }
+
+    /// the dot product of two vectors
+    pub fn dot(&self, a: impl Into<Self>) -> i32 {
+        let a = a.into();
+        self.0 * a.0 + self.1 * a.1
+    }
+
+    /// the length of the vector
+    pub fn magnitude(&self) -> f64 {
+        (self.dot(self) as f64).sqrt()
+    }
+
+    /// the smallest of each component of two vectors.
+    /// unlike `Ord::min`, which compares vectors by line then column
+    pub fn component_min(&self, a: impl Into<Self>) -> Self {
+        let a = a.into();
+        Self(self.0.min(a.0), self.1.min(a.1))
+    }
+
+    /// the largest of each component of two vectors.
+    /// unlike `Ord::max`, which compares vectors by line then column
+    pub fn component_max(&self, a: impl Into<Self>) -> Self {
+        let a = a.into();
+        Self(self.0.max(a.0), self.1.max(a.1))
+    }
+}
+
+impl std::ops::Add for Vector2 {
+    type Output = Self;
+    fn add(self, a: Self) -> Self {
+        Self(self.0 + a.0, self.1 + a.1)
+    }
+}
+
+impl std::ops::Sub for Vector2 {
+    type Output = Self;
+    fn sub(self, a: Self) -> Self {
+        Self(self.0 - a.0, self.1 - a.1)
+    }
+}
+
+impl std::ops::Mul<i32> for Vector2 {
+    type Output = Self;
+    fn mul(self, a: i32) -> Self {
+        Self(self.0 * a, self.1 * a)
+    }
+}
+
+/// divides each component, rounding towards zero
+impl std::ops::Div<i32> for Vector2 {
+    type Output = Self;
+    fn div(self, a: i32) -> Self {
+        Self(self.0 / a, self.1 / a)
+    }
+}
+
+impl std::ops::Neg for Vector2 {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self(-self.0, -self.1)
+    }
 }
 
 impl From<&Vector2> for Vector2 {
@@ -88,12 +250,96 @@ impl From<(i32, i32)> for Vector2 {
     }
 }
 
+/// A location in the buffer described relative to its contents
+/// rather than as a raw coordinate
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Position {
+    /// the beginning of a line, out of range lines are clamped to the first or last line
+    Line(i32),
+    /// the next occurrence of `target` on the current line.
+    /// when `till` is set the cursor stops one character short of the target
+    FindChar {
+        target: char,
+        forward: bool,
+        till: bool,
+    },
+    /// the end of the current line
+    LineEnd,
+    /// the first character of the current line that is not a space or tab
+    FirstNonBlank,
+    /// the next empty line after the current paragraph, or the last line
+    NextParagraph,
+    /// the previous empty line before the current paragraph, or the first line
+    PreviousParagraph,
+    /// the bracket matching the one under the cursor, or the first one after it on the line
+    MatchingBracket,
+}
+
+/// How the text between the selection start and the cursor is selected
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SelectionKind {
+    /// every character between the selection start and the cursor
+    Char,
+    /// every line between the selection start and the cursor, including the trailing newline
+    Line,
+    /// the columns between the selection start and the cursor on every line between them
+    Block,
+}
+
+/// The characters separating lines when the buffer is converted to a string
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LineEnding {
+    /// \n
+    Lf,
+    /// \r\n
+    CrLf,
+}
+
+impl LineEnding {
+    /// the line ending used by most lines of `text`, \n when there are no line endings
+    pub fn detect(text: &str) -> LineEnding {
+        let crlf = text.matches("\r\n").count();
+        if crlf > text.matches('\n').count() - crlf {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+impl std::fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineEnding::Lf => write!(f, "LF"),
+            LineEnding::CrLf => write!(f, "CRLF"),
+        }
+    }
+}
+
 /// Editor state information
 pub struct Editor {
     buffer: Grid,
     cursor: Vector2,
     select_start: Option<Vector2>,
+    select_kind: SelectionKind,
     selecting: bool,
+    // the buffer has changed since it was last saved
+    modified: bool,
+    // the number of changes made to the buffer
+    edits: u64,
+    /// the line ending written between lines when converted to a string
+    pub line_ending: LineEnding,
+    /// a byte order mark is written before the text when converted to a string
+    pub bom: bool,
+    // named locations, moved along with the lines they are on
+    marks: HashMap<char, Vector2>,
 }
 
 /// Create an editor for types which implement Into<String>
@@ -102,6 +348,15 @@ impl<T: Into<String>> From<T> for Editor {
         let mut editor = Editor::new();
         let s = s.into();
 
+        // the byte order mark is kept out of the buffer and restored when converted to a string
+        let s = match s.strip_prefix('\u{feff}') {
+            Some(text) => {
+                editor.bom = true;
+                text
+            }
+            None => &s[..],
+        };
+
         editor.buffer = s
             .lines()
             .map(|x| x.chars().map(|x| CharCel::from(x)).collect())
@@ -111,6 +366,7 @@ impl<T: Into<String>> From<T> for Editor {
         if s.ends_with('\n') || s.ends_with("\r\n") {
             editor.buffer.push(Vec::new());
         }
+        editor.line_ending = LineEnding::detect(s);
 
         editor
     }
@@ -123,7 +379,13 @@ impl Editor {
             buffer: Grid::new(),
             cursor: Vector2(0, 0),
             select_start: None,
+            select_kind: SelectionKind::Char,
             selecting: false,
+            modified: false,
+            edits: 0,
+            line_ending: LineEnding::Lf,
+            bom: false,
+            marks: HashMap::new(),
         };
     }
 
@@ -131,6 +393,68 @@ impl Editor {
         self.cursor
     }
 
+    /// whether the buffer has been changed since it was last saved
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// mark the buffer as changed, or as saved when `modified` is false
+    pub fn set_modified(&mut self, modified: bool) {
+        self.modified = modified;
+    }
+
+    /// return the number of changes made to the buffer, which only ever grows
+    pub fn edit_count(&self) -> u64 {
+        self.edits
+    }
+
+    /// mark the buffer as changed by an edit
+    fn mark_modified(&mut self) {
+        self.modified = true;
+        self.edits += 1;
+    }
+
+    /// return the number of lines in the buffer
+    pub fn line_count(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Name the cursor's location `name`, replacing any location given the name before
+    pub fn set_mark(&mut self, name: char) {
+        self.marks.insert(name, self.cursor);
+    }
+
+    /// return the location named `name`, if the mark is set and its line still exists
+    pub fn mark(&self, name: char) -> Option<Vector2> {
+        self.marks.get(&name).map(|&x| self.clamp_vector(x))
+    }
+
+    /// move the marks after `removed` lines starting at `first` were replaced by `added` lines.
+    /// marks on the lines which are gone are moved to the last new line,
+    /// or removed if no lines took their place
+    fn lines_replaced(&mut self, first: i32, removed: i32, added: i32) {
+        self.marks.retain(|_, mark| {
+            if mark.y() >= first + removed {
+                mark.1 += added - removed;
+            } else if mark.y() >= first + added {
+                mark.1 = first + added - 1;
+                return added > 0;
+            }
+            true
+        });
+    }
+
+    /// return the number of words in the buffer, runs of characters between whitespace
+    pub fn word_count(&self) -> usize {
+        self.buffer.iter().map(|row| count_words(row)).sum()
+    }
+
+    /// return the number of characters in the buffer, counting the newline between lines
+    pub fn char_count(&self) -> usize {
+        let newlines = self.buffer.len().saturating_sub(1);
+        self.buffer.iter().map(|row| row.len()).sum::<usize>() + newlines
+    }
+
     /// return the length of the current line or zero if not currently on a line
     pub fn line_len(&self) -> usize {
         self.buffer
@@ -142,12 +466,39 @@ impl Editor {
     ///
     /// # Returns
     /// A vector storing the position of the cursor after clamping it to valid coordinates
+    /// the cursor is kept off the middle of a grapheme cluster, moving past it in the direction
+    /// of the motion
     pub fn move_cursor(&mut self, direction: impl Into<Vector2>) -> Vector2 {
-        let new_pos = self.clamp_vector(self.cursor.add(&direction.into()));
+        let direction = direction.into();
+        let mut new_pos = self.clamp_vector(self.cursor.add(direction));
+        while !self.is_cluster_boundary(new_pos) {
+            new_pos.0 += if direction.x() > 0 { 1 } else { -1 };
+        }
         self.cursor = new_pos;
         return new_pos;
     }
 
+    /// return true if `location` is not between two characters of the same grapheme cluster
+    fn is_cluster_boundary(&self, location: Vector2) -> bool {
+        let Vector2(x, y) = location;
+        let row = match self.get_row(y) {
+            Some(row) if x > 0 && (x as usize) < row.len() => row,
+            _ => return true,
+        };
+        let (before, after) = (row[x as usize - 1].char, row[x as usize].char);
+        if extends_cluster(after) || before == '\u{200D}' {
+            return false;
+        }
+
+        // regional indicators pair up from the start of a run of them
+        let indicators = row[..x as usize]
+            .iter()
+            .rev()
+            .take_while(|cell| is_regional_indicator(cell.char))
+            .count();
+        !(is_regional_indicator(after) && indicators % 2 == 1)
+    }
+
     /// Set the cursor position to a specific coordinate
     ///
     /// # Returns
@@ -158,6 +509,291 @@ impl Editor {
         new_pos
     }
 
+    /// Move the cursor to a position in the buffer
+    ///
+    /// # Returns
+    /// A vector storing the position of the cursor after clamping it to valid coordinates
+    pub fn move_cursor_to(&mut self, position: Position) -> Vector2 {
+        self.set_cursor(self.position_of(position))
+    }
+
+    /// return the coordinates of a position in the buffer without moving the cursor
+    pub fn position_of(&self, position: Position) -> Vector2 {
+        let y = self.cursor.y();
+        let location = match position {
+            Position::Line(line) => Vector2(0, line),
+            Position::FindChar {
+                target,
+                forward,
+                till,
+            } => match self.find_char(target, forward) {
+                Some(x) if till && forward => Vector2(x - 1, y),
+                Some(x) if till => Vector2(x + 1, y),
+                Some(x) => Vector2(x, y),
+                None => self.cursor,
+            },
+            Position::LineEnd => Vector2(self.line_len() as i32, y),
+            Position::FirstNonBlank => Vector2(self.current_indent().chars().count() as i32, y),
+            Position::NextParagraph => Vector2(0, self.find_paragraph(true)),
+            Position::PreviousParagraph => Vector2(0, self.find_paragraph(false)),
+            Position::MatchingBracket => self.matching_bracket(self.cursor).unwrap_or(self.cursor),
+        };
+        self.clamp_vector(location)
+    }
+
+    /// Add `delta` to the number under the cursor, or the first one after it on the line.
+    /// the cursor is left on the last digit of the new number
+    ///
+    /// # Returns
+    /// false if there was no number to change
+    pub fn adjust_number(&mut self, delta: i64) -> bool {
+        let Vector2(x, y) = self.cursor;
+        let row = match self.buffer.get_mut(y as usize) {
+            Some(row) => row,
+            None => return false,
+        };
+        let is_digit = |i: usize| matches!(row.get(i), Some(c) if c.char.is_ascii_digit());
+
+        // find the digits under or after the cursor
+        let mut start = x as usize;
+        while is_digit(start) && start > 0 && is_digit(start - 1) {
+            start -= 1;
+        }
+        while start < row.len() && !is_digit(start) {
+            start += 1;
+        }
+        if start >= row.len() {
+            return false;
+        }
+        let mut end = start;
+        while is_digit(end) {
+            end += 1;
+        }
+        if start > 0 && row[start - 1].char == '-' {
+            start -= 1;
+        }
+
+        let text: String = row[start..end].iter().map(|c| c.char).collect();
+        let number = match text.parse::<i64>() {
+            Ok(n) => n.saturating_add(delta),
+            Err(_) => return false,
+        };
+        let number = number.to_string();
+        let len = number.chars().count();
+        row.splice(start..end, number.chars().map(CharCel::from));
+        self.mark_modified();
+
+        self.set_cursor(((start + len - 1) as i32, y));
+        true
+    }
+
+    /// Change the case of the character under the cursor and move past it
+    ///
+    /// # Returns
+    /// false if the cursor is at the end of its line
+    pub fn change_case_at_cursor(&mut self, case: Case) -> bool {
+        let Vector2(x, y) = self.cursor;
+        match self
+            .buffer
+            .get_mut(y as usize)
+            .and_then(|row| row.get_mut(x as usize))
+        {
+            Some(cell) => cell.char = case.apply(cell.char),
+            None => return false,
+        }
+        self.mark_modified();
+        self.set_cursor((x + 1, y));
+        true
+    }
+
+    /// Change the case of every selected character and end the selection
+    pub fn change_case_selection(&mut self, case: Case) {
+        if !self.selecting {
+            return;
+        }
+        let (first, last) = self.selected_lines();
+        for y in first..=last {
+            // an empty buffer has no lines to change
+            let len = self.get_row(y).map_or(0, Vec::len);
+            for x in 0..len {
+                if self.is_selected((x as i32, y)) {
+                    let cell = &mut self.buffer[y as usize][x];
+                    cell.char = case.apply(cell.char);
+                }
+            }
+        }
+        self.mark_modified();
+        self.clear_selection();
+    }
+
+    /// Overwrite the character under the cursor without moving it
+    ///
+    /// # Returns
+    /// false if the cursor is at the end of its line and nothing was replaced
+    pub fn replace_char(&mut self, content: char) -> bool {
+        let Vector2(x, y) = self.cursor;
+        match self
+            .buffer
+            .get_mut(y as usize)
+            .and_then(|row| row.get_mut(x as usize))
+        {
+            Some(cell) => cell.char = content,
+            None => return false,
+        }
+        self.mark_modified();
+        true
+    }
+
+    /// Delete the characters between the cursor and a position on the cursor's line,
+    /// leaving the cursor at the start of the deleted text
+    ///
+    /// # Returns
+    /// The deleted characters
+    pub fn delete_to(&mut self, position: Position) -> Vec<CharCel> {
+        let Vector2(x, y) = self.cursor;
+        let target = self.position_of(position).x();
+        let (start, end) = (min(x, target) as usize, x.max(target) as usize);
+
+        let deleted = match self.buffer.get_mut(y as usize) {
+            Some(row) => row.drain(start..end.min(row.len())).collect(),
+            None => Vec::new(),
+        };
+        if !deleted.is_empty() {
+            self.mark_modified();
+        }
+        self.set_cursor((start as i32, y));
+        deleted
+    }
+
+    /// Delete up to `count` characters on the cursor's line, from the cursor onwards when
+    /// `forward` is set and before the cursor otherwise. the newline is never deleted,
+    /// and accents combined with a character are deleted along with it
+    ///
+    /// # Returns
+    /// The deleted characters
+    pub fn delete_chars(&mut self, count: usize, forward: bool) -> Vec<CharCel> {
+        let Vector2(x, y) = self.cursor;
+        let row = match self.buffer.get_mut(y as usize) {
+            Some(row) => row,
+            None => return Vec::new(),
+        };
+        let x = (x.max(0) as usize).min(row.len());
+
+        let (mut start, mut end) = if forward {
+            (x, (x + count).min(row.len()))
+        } else {
+            (x.saturating_sub(count), x)
+        };
+        while end < row.len() && extends_cluster(row[end].char) {
+            end += 1;
+        }
+        while start > 0 && start < row.len() && extends_cluster(row[start].char) {
+            start -= 1;
+        }
+
+        let deleted: Vec<CharCel> = row.drain(start..end).collect();
+        if !deleted.is_empty() {
+            self.mark_modified();
+        }
+        self.set_cursor((start as i32, y));
+        deleted
+    }
+
+    /// return the line of the next empty line following a non empty one,
+    /// searching down when `forward` is set and up otherwise
+    fn find_paragraph(&self, forward: bool) -> i32 {
+        let last = self.buffer.len() as i32 - 1;
+        let step = if forward { 1 } else { -1 };
+        let is_empty = |y: i32| self.buffer[y as usize].is_empty();
+
+        let in_bounds = |y: i32| y >= 0 && y <= last;
+        if !in_bounds(self.cursor.y()) {
+            return self.cursor.y().max(0).min(last);
+        }
+
+        let mut y = self.cursor.y();
+        // skip the empty lines between the cursor and the paragraph
+        while is_empty(y) && in_bounds(y + step) {
+            y += step;
+        }
+        while !is_empty(y) && in_bounds(y + step) {
+            y += step;
+        }
+        y
+    }
+
+    /// Return the location of the bracket matching the one at `location`.
+    /// when `location` is not on a bracket the first bracket after it on the line is matched
+    ///
+    /// # Returns
+    /// None if there is no bracket or it is unmatched
+    pub fn matching_bracket(&self, location: Vector2) -> Option<Vector2> {
+        const PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+        let Vector2(x, y) = self.clamp_vector(location);
+        let row = self.buffer.get(y as usize)?;
+        let (x, bracket) = row
+            .iter()
+            .enumerate()
+            .skip(x as usize)
+            .find(|(_, cell)| PAIRS.iter().any(|&(a, b)| cell.char == a || cell.char == b))
+            .map(|(i, cell)| (i as i32, cell.char))?;
+
+        let forward = PAIRS.iter().any(|&(open, _)| open == bracket);
+        let (open, close) = *PAIRS.iter().find(|&&(a, b)| a == bracket || b == bracket)?;
+        let (same, other) = if forward {
+            (open, close)
+        } else {
+            (close, open)
+        };
+
+        let mut depth = 0;
+        let mut y = y;
+        let mut x = x;
+        loop {
+            let row = &self.buffer[y as usize];
+            while x >= 0 && (x as usize) < row.len() {
+                let c = row[x as usize].char;
+                if c == same {
+                    depth += 1;
+                } else if c == other {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(Vector2(x, y));
+                    }
+                }
+                x += if forward { 1 } else { -1 };
+            }
+
+            // continue on the next or previous line
+            y += if forward { 1 } else { -1 };
+            let row = self.buffer.get(y as usize)?;
+            x = if forward { 0 } else { row.len() as i32 - 1 };
+        }
+    }
+
+    /// return the column of the next occurrence of `target` on the cursor's line
+    /// searching to the right of the cursor when `forward` is set, and to the left otherwise
+    fn find_char(&self, target: char, forward: bool) -> Option<i32> {
+        let Vector2(x, y) = self.cursor;
+        let row = self.buffer.get(y as usize)?;
+
+        if forward {
+            row.iter()
+                .enumerate()
+                .skip((x + 1) as usize)
+                .find(|(_, cell)| cell.char == target)
+                .map(|(i, _)| i as i32)
+        } else {
+            row.iter()
+                .enumerate()
+                .take(x as usize)
+                .rev()
+                .find(|(_, cell)| cell.char == target)
+                .map(|(i, _)| i as i32)
+        }
+    }
+
     /// clamps a vector to valid grid coordinate
     pub fn clamp_vector(&self, v: Vector2) -> Vector2 {
         let Vector2(x, y) = v;
@@ -206,7 +842,77 @@ impl Editor {
     /// set the selection to start from the given coordinate
     pub fn begin_select_at(&mut self, loc: impl Into<Vector2>) {
         self.select_start = Some(loc.into());
+        self.select_kind = SelectionKind::Char;
+        self.selecting = true;
+    }
+
+    /// begin selecting whole lines from the current line of the cursor
+    pub fn begin_line_select(&mut self) {
+        self.begin_select();
+        self.select_kind = SelectionKind::Line;
+    }
+
+    /// select every line of the buffer, leaving the cursor at the end of the last line
+    pub fn select_all(&mut self) {
+        self.select_start = Some(Vector2(0, 0));
+        self.select_kind = SelectionKind::Line;
         self.selecting = true;
+        let last = self.buffer.len() as i32 - 1;
+        self.set_cursor((i32::MAX, last));
+    }
+
+    /// begin selecting the rectangle between the cursor and its future location
+    pub fn begin_block_select(&mut self) {
+        self.begin_select();
+        self.select_kind = SelectionKind::Block;
+    }
+
+    /// return true if a selection is in progress
+    pub fn is_selecting(&self) -> bool {
+        self.selecting
+    }
+
+    pub fn selection_kind(&self) -> SelectionKind {
+        self.select_kind
+    }
+
+    /// return the start and end of the selection, with the start before the end
+    pub fn selection_range(&self) -> Option<(Vector2, Vector2)> {
+        use std::cmp::{max, min};
+        match self.select_start {
+            Some(start) if self.selecting => {
+                let start = self.clamp_vector(start);
+                Some((min(start, self.cursor), max(start, self.cursor)))
+            }
+            _ => None,
+        }
+    }
+
+    /// return true if the cell at `location` is part of the selection.
+    /// the cell just past the end of a line stands for its newline
+    pub fn is_selected(&self, location: impl Into<Vector2>) -> bool {
+        let location = location.into();
+        let (start, end) = match self.selection_range() {
+            Some(x) => x,
+            None => return false,
+        };
+
+        match self.select_kind {
+            SelectionKind::Char => {
+                let line_len = self
+                    .get_row(location.y())
+                    .map_or(-1, |row| row.len() as i32);
+                location.x() >= 0 && location.x() <= line_len && location >= start && location < end
+            }
+            SelectionKind::Line => location.y() >= start.y() && location.y() <= end.y(),
+            SelectionKind::Block => {
+                let (corner1, corner2) = Self::block_corners(start, end);
+                location.y() >= corner1.y()
+                    && location.y() <= corner2.y()
+                    && location.x() >= corner1.x()
+                    && location.x() < corner2.x()
+            }
+        }
     }
 
     /// Clear the currently selected location.
@@ -217,21 +923,81 @@ impl Editor {
 
     /// copy the selected text
     pub fn copy(&self) -> Option<Vec<CharCel>> {
-        if self.selecting {
-            Some(self.copy_range(self.select_start.unwrap(), self.cursor))
-        } else {
-            None
+        if !self.selecting {
+            return None;
+        }
+
+        match self.select_kind {
+            SelectionKind::Char => Some(self.copy_range(self.select_start.unwrap(), self.cursor)),
+            SelectionKind::Line => {
+                let (first, last) = self.selected_lines();
+                Some(self.copy_lines(first, last))
+            }
+            SelectionKind::Block => Some(self.copy_block(self.select_start.unwrap(), self.cursor)),
         }
     }
 
     /// cut the selected text
     pub fn cut(&mut self) -> Option<Vec<CharCel>> {
-        if self.selecting {
-            self.selecting = false;
-            Some(self.cut_range(self.select_start.unwrap(), self.cursor))
-        } else {
-            None
+        if !self.selecting {
+            return None;
+        }
+
+        self.selecting = false;
+        match self.select_kind {
+            SelectionKind::Char => Some(self.cut_range(self.select_start.unwrap(), self.cursor)),
+            SelectionKind::Line => {
+                let (first, last) = self.selected_lines();
+                Some(self.cut_lines(first, last))
+            }
+            SelectionKind::Block => Some(self.cut_block(self.select_start.unwrap(), self.cursor)),
+        }
+    }
+
+    /// return the first and last lines touched by the selection
+    ///
+    /// # Panics
+    /// If `select_start` is `None`
+    fn selected_lines(&self) -> (i32, i32) {
+        use std::cmp::{max, min};
+        let start = self.clamp_vector(self.select_start.unwrap());
+        (
+            min(start.y(), self.cursor.y()),
+            max(start.y(), self.cursor.y()),
+        )
+    }
+
+    /// copy the lines from `first` to `last` inclusive, each followed by a newline
+    pub fn copy_lines(&self, first: i32, last: i32) -> Vec<CharCel> {
+        let mut data = Vec::new();
+        for line in first..=last {
+            if let Some(row) = self.buffer.get(line as usize) {
+                data.extend_from_slice(row);
+                data.push(CharCel::from('\n'));
+            }
+        }
+        data
+    }
+
+    /// remove the lines from `first` to `last` inclusive and move the cursor
+    /// to the beginning of the line that took their place
+    pub fn cut_lines(&mut self, first: i32, last: i32) -> Vec<CharCel> {
+        let data = self.copy_lines(first, last);
+
+        let len = self.buffer.len() as i32;
+        let (first, last) = (first.max(0), last.min(len - 1));
+        if first <= last {
+            self.buffer.drain(first as usize..=last as usize);
+            self.lines_replaced(first, last - first + 1, 0);
+            // cutting every line leaves an empty one, as an empty file has
+            if self.buffer.is_empty() {
+                self.buffer.push(Vec::new());
+            }
+            self.mark_modified();
         }
+
+        self.set_cursor((0, first));
+        data
     }
 
     /// return the character at location
@@ -282,9 +1048,62 @@ impl Editor {
         data
     }
 
-    /// cut the text from location from, to location to
-    pub fn cut_range<T: Into<Vector2>>(&mut self, from: T, to: T) -> Vec<CharCel> {
-        use std::cmp::{max, min};
+    /// return the top left and bottom right corners of the rectangle between two coordinates
+    fn block_corners(from: Vector2, to: Vector2) -> (Vector2, Vector2) {
+        (from.component_min(to), from.component_max(to))
+    }
+
+    /// Copy the columns between `from` and `to` on every line between them.
+    /// Each line's slice is separated by a newline
+    pub fn copy_block<T: Into<Vector2>>(&self, from: T, to: T) -> Vec<CharCel> {
+        let (start, end) =
+            Self::block_corners(self.clamp_vector(from.into()), self.clamp_vector(to.into()));
+
+        let mut data = Vec::new();
+        for y in start.y()..=end.y() {
+            // an empty buffer has no row to copy from
+            let row = match self.buffer.get(y as usize) {
+                Some(row) => row,
+                None => break,
+            };
+            let x1 = min(start.x() as usize, row.len());
+            let x2 = min(end.x() as usize, row.len());
+            data.extend_from_slice(&row[x1..x2]);
+            if y != end.y() {
+                data.push(CharCel::from('\n'));
+            }
+        }
+
+        data
+    }
+
+    /// Cut the columns between `from` and `to` on every line between them,
+    /// leaving the cursor at the top left corner of the block
+    pub fn cut_block<T: Into<Vector2>>(&mut self, from: T, to: T) -> Vec<CharCel> {
+        let (start, end) =
+            Self::block_corners(self.clamp_vector(from.into()), self.clamp_vector(to.into()));
+        let data = self.copy_block(start, end);
+
+        for y in start.y()..=end.y() {
+            let row = match self.buffer.get_mut(y as usize) {
+                Some(row) => row,
+                None => break,
+            };
+            let x1 = min(start.x() as usize, row.len());
+            let x2 = min(end.x() as usize, row.len());
+            row.drain(x1..x2);
+            if x1 < x2 {
+                self.mark_modified();
+            }
+        }
+
+        self.set_cursor(start);
+        data
+    }
+
+    /// cut the text from location from, to location to
+    pub fn cut_range<T: Into<Vector2>>(&mut self, from: T, to: T) -> Vec<CharCel> {
+        use std::cmp::{max, min};
 
         let (from, to) = (self.clamp_vector(from.into()), self.clamp_vector(to.into()));
 
@@ -330,6 +1149,769 @@ impl Editor {
         Vec::from(buffer)
     }
 
+    /// Join the cursor's line with the line below it, or join every selected line into one.
+    /// whitespace around each join is collapsed into a single space, and the cursor is left
+    /// at the last join
+    ///
+    /// # Returns
+    /// false if there was no line to join with
+    pub fn join_line(&mut self) -> bool {
+        let (first, last) = match self.selection_range() {
+            Some((start, end)) => (start.y(), end.y().max(start.y() + 1)),
+            None => (self.cursor.y(), self.cursor.y() + 1),
+        };
+        if first < 0 || last as usize >= self.buffer.len() {
+            return false;
+        }
+        self.clear_selection();
+
+        let y = first as usize;
+        let mut seam = 0;
+        for _ in first..last {
+            let next = self.buffer.remove(y + 1);
+            let row = &mut self.buffer[y];
+            while matches!(row.last().map(|x| x.char), Some(' ') | Some('\t')) {
+                row.pop();
+            }
+            seam = row.len();
+
+            let next: Vec<CharCel> = next
+                .into_iter()
+                .skip_while(|x| x.char == ' ' || x.char == '\t')
+                .collect();
+            if !row.is_empty() && !next.is_empty() {
+                row.push(CharCel::from(' '));
+            }
+            row.extend(next);
+        }
+        self.lines_replaced(first, last - first + 1, 1);
+        self.mark_modified();
+
+        self.set_cursor((seam as i32, first));
+        true
+    }
+
+    /// Insert a copy of the cursor's line, or of every selected line, below the original.
+    /// the cursor and selection move onto the copy
+    pub fn duplicate_line(&mut self) {
+        let (first, last) = match self.selection_range() {
+            Some((start, end)) => (start.y(), end.y()),
+            None => (self.cursor.y(), self.cursor.y()),
+        };
+        let lines = self.line_indices(first..=last);
+        if lines.is_empty() {
+            return;
+        }
+
+        let copy: Vec<Vec<CharCel>> = self.buffer[lines.clone()].to_vec();
+        let count = copy.len() as i32;
+        self.buffer.splice(lines.end..lines.end, copy);
+        self.lines_replaced(lines.end as i32, 0, count);
+        self.mark_modified();
+
+        self.cursor.1 += count;
+        if let Some(ref mut start) = self.select_start {
+            start.1 += count;
+        }
+    }
+
+    /// Move the lines in `lines` up or down by `delta` rows, along with the cursor and selection.
+    /// lines are not moved past the top of the buffer or the empty line after its final newline
+    ///
+    /// # Returns
+    /// true if the lines were moved
+    pub fn move_lines(&mut self, lines: RangeInclusive<i32>, delta: i32) -> bool {
+        let mut len = self.buffer.len() as i32;
+        if matches!(self.buffer.last(), Some(row) if row.is_empty()) {
+            len -= 1;
+        }
+        let (first, last) = (*lines.start(), *lines.end());
+        if delta == 0 || first < 0 || first > last || first + delta < 0 || last + delta >= len {
+            return false;
+        }
+
+        if delta > 0 {
+            self.buffer[first as usize..=(last + delta) as usize].rotate_right(delta as usize);
+        } else {
+            self.buffer[(first + delta) as usize..=last as usize].rotate_left(-delta as usize);
+        }
+        self.mark_modified();
+
+        let follow = |v: &mut Vector2| {
+            if v.1 >= first && v.1 <= last {
+                v.1 += delta;
+            }
+        };
+        follow(&mut self.cursor);
+        if let Some(ref mut start) = self.select_start {
+            follow(start);
+        }
+        true
+    }
+
+    /// Prepend `width` spaces to every non empty line in `lines`
+    pub fn indent_lines(&mut self, lines: RangeInclusive<i32>, width: usize) {
+        for y in self.line_indices(lines) {
+            if self.buffer[y].is_empty() {
+                continue;
+            }
+            self.buffer[y].splice(0..0, (0..width).map(|_| CharCel::from(' ')));
+            self.shift_columns(y as i32, width as i32);
+            self.mark_modified();
+        }
+    }
+
+    /// Comment out the lines in `lines` by inserting `prefix` after their indentation,
+    /// or remove it when every line is already commented. lines of only whitespace are skipped,
+    /// and a commented line may leave out the whitespace at the end of `prefix`
+    ///
+    /// # Returns
+    /// true if the lines were commented and false if they were uncommented
+    pub fn toggle_comment(&mut self, lines: RangeInclusive<i32>, prefix: &str) -> bool {
+        let prefix: Vec<char> = prefix.chars().collect();
+        let bare = prefix
+            .iter()
+            .rposition(|c| !c.is_whitespace())
+            .map_or(0, |x| x + 1);
+
+        // the indentation of each line with text, and how much of the prefix follows it
+        let mut commented = Vec::new();
+        for y in self.line_indices(lines) {
+            let row = &self.buffer[y];
+            let indent = match row.iter().position(|x| !x.char.is_whitespace()) {
+                Some(indent) => indent,
+                None => continue,
+            };
+            let text = &row[indent..];
+            let matching = if text.len() >= prefix.len()
+                && text.iter().zip(&prefix).all(|(x, &c)| x.char == c)
+            {
+                Some(prefix.len())
+            } else if bare > 0
+                && text
+                    .iter()
+                    .take(bare)
+                    .map(|x| x.char)
+                    .eq(prefix[..bare].iter().cloned())
+            {
+                Some(bare)
+            } else {
+                None
+            };
+            commented.push((y, indent, matching));
+        }
+
+        let uncomment = !commented.is_empty() && commented.iter().all(|x| x.2.is_some());
+        for (y, indent, matching) in commented {
+            if uncomment {
+                let len = matching.unwrap();
+                self.buffer[y].drain(indent..indent + len);
+                self.shift_columns(y as i32, -(len as i32));
+            } else {
+                let cells = prefix.iter().map(|&c| CharCel::from(c));
+                self.buffer[y].splice(indent..indent, cells);
+                self.shift_columns(y as i32, prefix.len() as i32);
+            }
+            self.mark_modified();
+        }
+        !uncomment
+    }
+
+    /// Remove up to `width` leading spaces or a single leading tab from every line in `lines`
+    pub fn dedent_lines(&mut self, lines: RangeInclusive<i32>, width: usize) {
+        for y in self.line_indices(lines) {
+            let row = &mut self.buffer[y];
+            let count = if row.first().map(|x| x.char) == Some('\t') {
+                1
+            } else {
+                row.iter().take(width).take_while(|x| x.char == ' ').count()
+            };
+            row.drain(..count);
+            self.shift_columns(y as i32, -(count as i32));
+            if count > 0 {
+                self.mark_modified();
+            }
+        }
+    }
+
+    /// clamp `lines` to the rows of the buffer
+    fn line_indices(&self, lines: RangeInclusive<i32>) -> Range<usize> {
+        let first = (*lines.start()).max(0) as usize;
+        let last = ((*lines.end() + 1).max(0) as usize).min(self.buffer.len());
+        first..last.max(first)
+    }
+
+    /// move the cursor and selection start on line `y` along with text shifted by `amount`
+    fn shift_columns(&mut self, y: i32, amount: i32) {
+        let shift = |v: &mut Vector2| {
+            if v.1 == y {
+                v.0 = (v.0 + amount).max(0);
+            }
+        };
+        shift(&mut self.cursor);
+        if let Some(ref mut start) = self.select_start {
+            shift(start);
+        }
+    }
+
+    /// Return the leading spaces and tabs of the cursor's line
+    pub fn current_indent(&self) -> String {
+        self.get_row(self.cursor.y())
+            .map(|row| {
+                row.iter()
+                    .map(|x| x.char)
+                    .take_while(|&c| c == ' ' || c == '\t')
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Return the number of characters a backspace should delete.
+    /// when only indentation is before the cursor this reaches back to the previous
+    /// multiple of `width` columns, otherwise it is a single character
+    pub fn backspace_width(&self, width: usize) -> usize {
+        let x = self.cursor.x() as usize;
+        let indent = self.current_indent();
+        if x == 0 || x > indent.chars().count() || width == 0 {
+            return 1;
+        }
+
+        let before: Vec<char> = indent.chars().take(x).collect();
+        let column: usize = before
+            .iter()
+            .map(|&c| if c == '\t' { width } else { 1 })
+            .sum();
+        let target = (column - 1) / width * width;
+
+        let mut count = 0;
+        let mut column = column;
+        for &c in before.iter().rev() {
+            if column <= target {
+                break;
+            }
+            column -= if c == '\t' { width } else { 1 };
+            count += 1;
+        }
+        count.max(1)
+    }
+
+    /// Return the number of spaces from the cursor to the next multiple of `width` columns,
+    /// with tabs before the cursor reaching to the next multiple themselves
+    pub fn tab_stop_width(&self, width: usize) -> usize {
+        if width == 0 {
+            return 0;
+        }
+        let x = self.cursor.x().max(0) as usize;
+        let column = self.get_row(self.cursor.y()).map_or(0, |row| {
+            row.iter().take(x).fold(0, |column, cell| match cell.char {
+                '\t' => column / width * width + width,
+                _ => column + 1,
+            })
+        });
+        width - column % width
+    }
+
+    /// Return the start of the first occurrence of `find` at or after `from`,
+    /// comparing letters regardless of case if `ignore_case` is set
+    pub fn search(
+        &self,
+        find: &str,
+        from: impl Into<Vector2>,
+        ignore_case: bool,
+    ) -> Option<Vector2> {
+        let fold = |c: char| if ignore_case { fold_case(c) } else { c };
+        let find: Vec<char> = find.chars().map(fold).collect();
+        if find.is_empty() {
+            return None;
+        }
+
+        let from = from.into();
+        for y in from.y().max(0)..self.buffer.len() as i32 {
+            let row = &self.buffer[y as usize];
+            let start = if y == from.y() {
+                from.x().max(0) as usize
+            } else {
+                0
+            };
+            if row.len() < find.len() {
+                continue;
+            }
+            let found = (start..=row.len() - find.len()).find(|&x| {
+                row[x..x + find.len()]
+                    .iter()
+                    .map(|c| fold(c.char))
+                    .eq(find.iter().copied())
+            });
+            if let Some(x) = found {
+                return Some(Vector2(x as i32, y));
+            }
+        }
+        None
+    }
+
+    /// Return the start of the first match of `pattern` after `from`,
+    /// or of the last match before `from` when `reverse` is set
+    pub fn search_regex(
+        &self,
+        pattern: &Pattern,
+        from: impl Into<Vector2>,
+        reverse: bool,
+    ) -> Option<Vector2> {
+        let from = from.into();
+        let line =
+            |y: i32| -> Vec<char> { self.buffer[y as usize].iter().map(|x| x.char).collect() };
+
+        if reverse {
+            let last = from.y().min(self.buffer.len() as i32 - 1);
+            for y in (0..=last).rev() {
+                let found = pattern
+                    .find_all(&line(y))
+                    .into_iter()
+                    .map(|x| x.start as i32)
+                    .rev()
+                    .find(|&x| y < from.y() || x < from.x());
+                if let Some(x) = found {
+                    return Some(Vector2(x, y));
+                }
+            }
+        } else {
+            for y in from.y().max(0)..self.buffer.len() as i32 {
+                let start = if y == from.y() {
+                    from.x().max(0) as usize
+                } else {
+                    0
+                };
+                if let Some(found) = pattern.find_at(&line(y), start) {
+                    return Some(Vector2(found.start as i32, y));
+                }
+            }
+        }
+        None
+    }
+
+    /// Return the start of every non empty match of `pattern` in the buffer
+    pub fn search_all(&self, pattern: &Pattern) -> Vec<Vector2> {
+        self.find_matches(pattern, 0..self.line_count() as i32)
+            .into_iter()
+            .map(|(start, _)| start)
+            .collect()
+    }
+
+    /// Search like `search_regex`, continuing from the other end of the buffer
+    /// when there is no match before reaching the end if `wrap` is set
+    ///
+    /// # Returns
+    /// The start of the match, and whether the search wrapped around to find it
+    pub fn search_wrapping(
+        &self,
+        pattern: &Pattern,
+        from: impl Into<Vector2>,
+        reverse: bool,
+        wrap: bool,
+    ) -> Option<(Vector2, bool)> {
+        if let Some(found) = self.search_regex(pattern, from, reverse) {
+            return Some((found, false));
+        }
+        if !wrap {
+            return None;
+        }
+
+        let from = if reverse {
+            Vector2(i32::MAX, self.line_count() as i32 - 1)
+        } else {
+            Vector2(0, 0)
+        };
+        self.search_regex(pattern, from, reverse)
+            .map(|found| (found, true))
+    }
+
+    /// Return the start and end of every match of `pattern` on `lines`, skipping empty matches
+    pub fn find_matches(&self, pattern: &Pattern, lines: Range<i32>) -> Vec<(Vector2, Vector2)> {
+        let mut matches = Vec::new();
+        for y in lines {
+            let row = match self.get_row(y) {
+                Some(row) if y >= 0 => row,
+                _ => continue,
+            };
+            let line: Vec<char> = row.iter().map(|x| x.char).collect();
+            matches.extend(
+                pattern
+                    .find_all(&line)
+                    .into_iter()
+                    .filter(|x| !x.is_empty())
+                    .map(|x| (Vector2(x.start as i32, y), Vector2(x.end as i32, y))),
+            );
+        }
+        matches
+    }
+
+    /// Replace the text between `start` and `end` on the same line with `text`,
+    /// leaving the cursor after the inserted text
+    pub fn replace_range(&mut self, start: Vector2, end: Vector2, text: &str) {
+        let (start, end) = (self.clamp_vector(start), self.clamp_vector(end));
+        if start.y() != end.y() {
+            return;
+        }
+        let row = &mut self.buffer[start.y() as usize];
+        let (from, to) = (
+            min(start.x(), end.x()) as usize,
+            start.x().max(end.x()) as usize,
+        );
+        row.splice(from..to, text.chars().map(CharCel::from));
+        self.mark_modified();
+        self.set_cursor(((from + text.chars().count()) as i32, start.y()));
+    }
+
+    /// Replace occurrences of `find` on every line with `replace`.
+    /// only the first occurrence on each line is replaced unless `global` is set
+    ///
+    /// # Returns
+    /// The number of replacements made
+    pub fn replace_all(&mut self, find: &str, replace: &str, global: bool) -> usize {
+        if find.is_empty() || find.contains('\n') {
+            return 0;
+        }
+
+        let mut count = 0;
+        for row in self.buffer.iter_mut() {
+            let line: String = row.iter().map(|x| x.char).collect();
+            let found = line.matches(find).count();
+            if found == 0 {
+                continue;
+            }
+
+            let (line, replaced) = if global {
+                (line.replace(find, replace), found)
+            } else {
+                (line.replacen(find, replace, 1), 1)
+            };
+            *row = line.chars().map(CharCel::from).collect();
+            count += replaced;
+        }
+
+        if count > 0 {
+            self.mark_modified();
+        }
+        self.set_cursor(self.cursor);
+        count
+    }
+
+    /// Sort the lines in `lines` alphabetically and move the cursor to the first of them.
+    /// an empty last line is left in place so the buffer keeps its final newline
+    pub fn sort_lines(
+        &mut self,
+        lines: RangeInclusive<i32>,
+        reverse: bool,
+        case_insensitive: bool,
+    ) {
+        let len = self.buffer.len() as i32;
+        let first = (*lines.start()).max(0);
+        let mut last = (*lines.end()).min(len - 1);
+        // the empty line after a final newline stays at the end
+        if last == len - 1 && self.get_row(last).is_some_and(Vec::is_empty) {
+            last -= 1;
+        }
+        if first > last {
+            return;
+        }
+
+        let key = |row: &Vec<CharCel>| -> String {
+            let line: String = row.iter().map(|x| x.char).collect();
+            if case_insensitive {
+                line.to_lowercase()
+            } else {
+                line
+            }
+        };
+
+        let rows = &mut self.buffer[first as usize..=last as usize];
+        rows.sort_by_key(|row| key(row));
+        if reverse {
+            rows.reverse();
+        }
+
+        self.mark_modified();
+        self.set_cursor((0, first));
+    }
+
+    /// Return the start and end of the word under the cursor, the end being one past its last
+    /// character. a word is a run of characters of the same `CharClass`.
+    /// `around` includes the whitespace after the word, or before it when there is none after,
+    /// and on whitespace it includes the word after the whitespace
+    ///
+    /// # Returns
+    /// None if the cursor's line is empty
+    pub fn word_object(&self, around: bool) -> Option<(Vector2, Vector2)> {
+        let y = self.cursor.y();
+        let row = self.get_row(y).filter(|row| !row.is_empty())?;
+        let class = |x: usize| CharClass::of(row[x].char);
+
+        // the end of the run of characters of the same class as the one at `x`
+        let run_end = |x: usize| {
+            let mut end = x;
+            while end < row.len() && class(end) == class(x) {
+                end += 1;
+            }
+            end
+        };
+
+        let x = (self.cursor.x().max(0) as usize).min(row.len() - 1);
+        let mut start = x;
+        while start > 0 && class(start - 1) == class(x) {
+            start -= 1;
+        }
+        let mut end = run_end(x);
+
+        if around {
+            if class(x) == CharClass::Whitespace {
+                if end < row.len() {
+                    end = run_end(end);
+                }
+            } else if end < row.len() && class(end) == CharClass::Whitespace {
+                end = run_end(end);
+            } else {
+                while start > 0 && class(start - 1) == CharClass::Whitespace {
+                    start -= 1;
+                }
+            }
+        }
+        Some((Vector2(start as i32, y), Vector2(end as i32, y)))
+    }
+
+    /// Return the start and end of the text between the delimiters `open` and `close` around the
+    /// cursor, the end being one past its last character. `around` includes the delimiters.
+    /// brackets are matched across lines skipping nested pairs, quotes, where `open` and `close`
+    /// are the same, pair up along the cursor's line and the next pair is used when the cursor
+    /// is not inside one
+    ///
+    /// # Returns
+    /// None if the cursor is not inside the delimiters
+    pub fn delimiter_object(
+        &self,
+        open: char,
+        close: char,
+        around: bool,
+    ) -> Option<(Vector2, Vector2)> {
+        let (start, end) = if open == close {
+            self.enclosing_quotes(open)?
+        } else {
+            let start = self.enclosing_bracket(open, close)?;
+            (start, self.matching_bracket(start)?)
+        };
+
+        if around {
+            Some((start, end.add((1, 0))))
+        } else {
+            Some((start.add((1, 0)), end))
+        }
+    }
+
+    /// return the location of the unmatched `open` bracket before the cursor,
+    /// or the bracket under the cursor
+    fn enclosing_bracket(&self, open: char, close: char) -> Option<Vector2> {
+        let char_at = |v: Vector2| self.get_cell(v).map(|x| x.char);
+        let cursor = self.clamp_vector(self.cursor);
+        match char_at(cursor) {
+            Some(c) if c == open => return Some(cursor),
+            Some(c) if c == close => return self.matching_bracket(cursor),
+            _ => {}
+        }
+
+        let mut depth = 0;
+        let Vector2(mut x, mut y) = cursor;
+        loop {
+            x -= 1;
+            while x < 0 {
+                y -= 1;
+                x = self.get_row(y)?.len() as i32 - 1;
+            }
+            match char_at(Vector2(x, y)) {
+                Some(c) if c == close => depth += 1,
+                Some(c) if c == open && depth == 0 => return Some(Vector2(x, y)),
+                Some(c) if c == open => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    /// return the locations of the pair of `quote` characters on the cursor's line around the
+    /// cursor, or the first pair after it
+    fn enclosing_quotes(&self, quote: char) -> Option<(Vector2, Vector2)> {
+        let Vector2(x, y) = self.cursor;
+        let quotes: Vec<i32> = self
+            .get_row(y)?
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.char == quote)
+            .map(|(i, _)| i as i32)
+            .collect();
+
+        let (start, end) = quotes
+            .chunks_exact(2)
+            .map(|pair| (pair[0], pair[1]))
+            .find(|&(_, end)| end >= x)?;
+        Some((Vector2(start, y), Vector2(end, y)))
+    }
+
+    /// Insert `open` before `start` and `close` at `end`, wrapping the text between them,
+    /// and move the cursor onto `open`
+    pub fn surround(&mut self, start: Vector2, end: Vector2, open: char, close: char) {
+        let start = self.clamp_vector(start);
+        let end = self.clamp_vector(end);
+        // the closing delimiter goes in first so it does not move where the opening one goes
+        self.write_at(end, close);
+        self.write_at(start, open);
+        self.clear_selection();
+        self.set_cursor(start);
+    }
+
+    /// Return the lines of the paragraph containing the cursor,
+    /// the lines around it up to the nearest empty lines
+    pub fn paragraph_lines(&self) -> RangeInclusive<i32> {
+        let is_empty = |y: i32| self.get_row(y).is_none_or(|row| row.is_empty());
+        let (mut first, mut last) = (self.cursor.y(), self.cursor.y());
+        if is_empty(first) {
+            return first..=last;
+        }
+        while !is_empty(first - 1) {
+            first -= 1;
+        }
+        while !is_empty(last + 1) {
+            last += 1;
+        }
+        first..=last
+    }
+
+    /// Rejoin the paragraphs in `lines` and break them again between words,
+    /// so no line is wider than `width` characters unless it is a single long word.
+    /// each paragraph keeps the indentation of its first line,
+    /// and the following lines take the indentation of its second line
+    ///
+    /// # Returns
+    /// The number of lines the paragraphs now take
+    pub fn reflow(&mut self, lines: RangeInclusive<i32>, width: usize) -> usize {
+        let range = self.line_indices(lines);
+        let first = range.start;
+        let indent_of = |row: &Vec<CharCel>| -> String {
+            row.iter()
+                .map(|x| x.char)
+                .take_while(|&c| c == ' ' || c == '\t')
+                .collect()
+        };
+
+        let mut reflowed: Vec<Vec<CharCel>> = Vec::new();
+        for (i, paragraph) in self.buffer[range.clone()]
+            .split(|row| row.is_empty())
+            .enumerate()
+        {
+            // keep the empty lines between paragraphs
+            if i > 0 {
+                reflowed.push(Vec::new());
+            }
+            let (first_indent, indent) = match paragraph {
+                [] => continue,
+                [row] => (indent_of(row), indent_of(row)),
+                [row, next, ..] => (indent_of(row), indent_of(next)),
+            };
+
+            let text: String = paragraph
+                .iter()
+                .map(|row| row.iter().map(|x| x.char).collect::<String>() + " ")
+                .collect();
+            let mut line = first_indent;
+            let mut has_word = false;
+            for word in text.split_whitespace() {
+                if has_word && line.chars().count() + 1 + word.chars().count() > width {
+                    reflowed.push(line.chars().map(CharCel::from).collect());
+                    line = indent.clone();
+                    has_word = false;
+                }
+                if has_word {
+                    line.push(' ');
+                }
+                line.push_str(word);
+                has_word = true;
+            }
+            reflowed.push(line.chars().map(CharCel::from).collect());
+        }
+
+        let count = reflowed.len();
+        self.lines_replaced(first as i32, range.len() as i32, count as i32);
+        self.buffer.splice(range, reflowed);
+        self.mark_modified();
+        self.clear_selection();
+        self.set_cursor((0, first as i32));
+        count
+    }
+
+    /// Replace the tabs in the indentation of every line with `width` spaces
+    ///
+    /// # Returns
+    /// The number of lines which were changed
+    pub fn expand_tabs(&mut self, width: usize) -> usize {
+        self.retab(width, |columns| vec![' '; columns])
+    }
+
+    /// Replace every `width` columns in the indentation of every line with a tab,
+    /// only the indentation is changed so that text aligned with spaces stays aligned
+    ///
+    /// # Returns
+    /// The number of lines which were changed
+    pub fn unexpand_tabs(&mut self, width: usize) -> usize {
+        self.retab(width, |columns| {
+            let mut indent = vec!['\t'; columns / width.max(1)];
+            indent.resize(indent.len() + columns % width.max(1), ' ');
+            indent
+        })
+    }
+
+    /// Rewrite the indentation of every line with the characters returned by `indent`,
+    /// which is given the number of columns the indentation covers.
+    /// tabs cover `width` columns
+    fn retab<F>(&mut self, width: usize, indent: F) -> usize
+    where
+        F: Fn(usize) -> Vec<char>,
+    {
+        let column_width = |c: char| if c == '\t' { width } else { 1 };
+        let mut changed = 0;
+
+        for y in 0..self.buffer.len() {
+            let row = &mut self.buffer[y];
+            let old_len = row
+                .iter()
+                .position(|x| x.char != ' ' && x.char != '\t')
+                .unwrap_or(row.len());
+            let old: Vec<char> = row[..old_len].iter().map(|x| x.char).collect();
+            let new = indent(old.iter().map(|&c| column_width(c)).sum());
+            if old == new {
+                continue;
+            }
+
+            row.splice(..old_len, new.iter().map(|&c| CharCel::from(c)));
+            changed += 1;
+            self.mark_modified();
+
+            // keep the cursor over the same column of the indentation, or the same text after it
+            if self.cursor.y() == y as i32 {
+                let x = self.cursor.x() as usize;
+                self.cursor.0 = if x >= old_len {
+                    (x - old_len + new.len()) as i32
+                } else {
+                    let column: usize = old[..x].iter().map(|&c| column_width(c)).sum();
+                    let mut drawn = 0;
+                    new.iter()
+                        .take_while(|&&c| {
+                            drawn += column_width(c);
+                            drawn <= column
+                        })
+                        .count() as i32
+                };
+            }
+        }
+
+        changed
+    }
+
     /// After writing, the cursor location will be moved `content.len()` characters to the right
     pub fn write(&mut self, content: char) {
         self.write_at(self.cursor.clone(), content);
@@ -352,6 +1934,7 @@ impl Editor {
     pub fn write_at(&mut self, location: impl Into<Vector2>, content: char) {
         let location = self.clamp_vector(location.into());
         let Vector2(x, y) = location;
+        self.mark_modified();
 
         // retrieve or create the row at location `y`
         // a row should only need to be created when the vector is empty
@@ -367,6 +1950,16 @@ impl Editor {
         // spawn a new line, moving all characters after it to the back
         // otherwise insert a character into the line at the cursor position
         if content == '\n' {
+            // marks after the break move down with the text
+            for mark in self.marks.values_mut() {
+                if mark.y() > y {
+                    mark.1 += 1;
+                } else if mark.y() == y && mark.x() >= x {
+                    *mark = Vector2(mark.x() - x, y + 1);
+                }
+            }
+
+            let row = &mut self.buffer[y as usize];
             if (x as usize) >= len {
                 self.buffer.insert((y + 1) as usize, Vec::new()); // insert an empty line
             } else {
@@ -393,7 +1986,9 @@ impl Editor {
     /// If `selecting` is true and `select_start` is `none`
     pub fn delete(&mut self) -> Option<CharCel> {
         // delete the entire selection if a current selection is in progress
-        if self.selecting {
+        if self.selecting && self.select_kind != SelectionKind::Char {
+            return self.cut().and_then(|x| x.last().copied());
+        } else if self.selecting {
             self.selecting = false;
             self.cut_range(self.select_start.unwrap(), self.cursor.clone());
         }
@@ -408,6 +2003,12 @@ impl Editor {
             0
         };
 
+        // the characters of a grapheme cluster are deleted along with the first one
+        while !self.is_cluster_boundary(self.cursor.add((-1, 0))) {
+            self.delete_at(self.cursor);
+            self.cursor.0 -= 1;
+        }
+
         // delete the character before the cursor
         let val = self.delete_at(self.cursor);
 
@@ -430,19 +2031,34 @@ impl Editor {
 
         if let Some(row) = self.buffer.get_mut(y as usize) {
             if x == 0 && y >= 1 {
+                // marks on the removed line move onto the end of the line above
+                let previous_len = self.buffer[(y - 1) as usize].len() as i32;
+                for mark in self.marks.values_mut() {
+                    if mark.y() > y {
+                        mark.1 -= 1;
+                    } else if mark.y() == y {
+                        *mark = Vector2(mark.x() + previous_len, y - 1);
+                    }
+                }
+
                 let mut x = self.buffer.remove(y as usize);
                 // append the current line to the previous line
                 self.buffer
                     .get_mut((y - 1) as usize)
                     .unwrap()
                     .append(&mut x);
+                self.mark_modified();
                 return Some(CharCel::from('\n'));
             } else if x != 0 && (x as usize) < row.len() {
-                return Some(row.remove((x - 1) as usize));
+                let removed = row.remove((x - 1) as usize);
+                self.mark_modified();
+                return Some(removed);
             } else if x != 0 && row.len() != 0 {
                 // if the cursor is in a location greater than the last location in the line
                 // delete the last element in the buffer
-                return Some(row.remove(row.len() - 1));
+                let removed = row.remove(row.len() - 1);
+                self.mark_modified();
+                return Some(removed);
             }
         }
 
@@ -458,8 +2074,11 @@ impl std::fmt::Display for Editor {
             .iter()
             .map(|x| x.iter().map(|x| x.char).collect::<String>())
             .collect::<Vec<String>>()
-            .join("\n");
+            .join(self.line_ending.as_str());
 
+        if self.bom {
+            write!(f, "\u{feff}")?;
+        }
         write!(f, "{}", text)
     }
 }
@@ -471,6 +2090,30 @@ mod test {
 
     const TEST_STRING: &'static str = include_str!("../resources/sample_text.txt");
 
+    #[test]
+    fn test_vector_ops() {
+        let (a, b) = (Vector2(3, 4), Vector2(-1, 2));
+        assert_eq!(a + b, a.add(b));
+        assert_eq!(a + b, Vector2(2, 6));
+        assert_eq!(a - b, Vector2(4, 2));
+        assert_eq!(a - b, a.add(-b));
+        assert_eq!(a * 2, Vector2(6, 8));
+        assert_eq!(a * 2 / 2, a);
+        assert_eq!(Vector2(-7, 7) / 2, Vector2(-3, 3));
+        assert_eq!(a.dot(b), 5);
+        assert_eq!(a.magnitude(), 5.0);
+    }
+
+    #[test]
+    fn test_vector_component_min_max() {
+        let (a, b) = (Vector2(-3, 4), Vector2(2, -5));
+        assert_eq!(a.component_min(b), Vector2(-3, -5));
+        assert_eq!(a.component_max(b), Vector2(2, 4));
+        assert_eq!(a.component_min(a), a);
+        assert_eq!(Vector2::zero(), Vector2::default());
+        assert_eq!(Vector2::zero().component_max(a), Vector2(0, 4));
+    }
+
     #[test]
     fn test_vector_cmp() {
         let test_cases = vec![
@@ -485,6 +2128,762 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_editor_move_cursor_to_line() {
+        let mut editor = Editor::from("first\nsecond\nthird");
+
+        assert_eq!(editor.move_cursor_to(Position::Line(1)), Vector2(0, 1));
+
+        // out of range lines are clamped to the first and last lines
+        assert_eq!(editor.move_cursor_to(Position::Line(100)), Vector2(0, 2));
+        assert_eq!(editor.move_cursor_to(Position::Line(-5)), Vector2(0, 0));
+    }
+
+    #[test]
+    fn test_editor_find_char() {
+        let mut editor = Editor::from("a.b.c\nd.e");
+
+        macro_rules! find {
+            ($target:expr, $forward:expr, $till:expr) => {
+                editor.move_cursor_to(Position::FindChar {
+                    target: $target,
+                    forward: $forward,
+                    till: $till,
+                })
+            };
+        }
+
+        assert_eq!(find!('.', true, false), Vector2(1, 0));
+        assert_eq!(find!('.', true, false), Vector2(3, 0));
+        assert_eq!(find!('a', false, true), Vector2(1, 0));
+        assert_eq!(find!('c', true, true), Vector2(3, 0));
+
+        // the search does not cross line boundaries
+        assert_eq!(find!('e', true, false), Vector2(3, 0));
+    }
+
+    #[test]
+    fn test_editor_line_select() {
+        let mut editor = Editor::from("one\ntwo\nthree\nfour");
+        let to_string = |x: Vec<CharCel>| x.iter().map(|x| x.char).collect::<String>();
+
+        editor.set_cursor((2, 1));
+        editor.begin_line_select();
+        editor.move_cursor((0, 1));
+
+        assert_eq!(to_string(editor.copy().unwrap()), "two\nthree\n");
+
+        // deleting removes the selected lines entirely
+        editor.delete();
+        assert_eq!(editor.to_string(), "one\nfour");
+        assert_eq!(editor.cursor, Vector2(0, 1));
+
+        // the last line is copied with a trailing newline
+        editor.begin_line_select();
+        assert_eq!(to_string(editor.cut().unwrap()), "four\n");
+        assert_eq!(editor.to_string(), "one");
+    }
+
+    #[test]
+    fn test_editor_block_select() {
+        let mut editor = Editor::from("abcd\nef\nghij");
+        let to_string = |x: Vec<CharCel>| x.iter().map(|x| x.char).collect::<String>();
+
+        // the anchor may be after the cursor, short lines contribute what they have
+        editor.set_cursor((3, 2));
+        editor.begin_block_select();
+        editor.set_cursor((1, 0));
+
+        assert_eq!(to_string(editor.copy().unwrap()), "bc\nf\nhi");
+
+        editor.delete();
+        assert_eq!(editor.to_string(), "ad\ne\ngj");
+        assert_eq!(editor.cursor, Vector2(1, 0));
+    }
+
+    #[test]
+    fn test_editor_empty_buffer() {
+        // a new file has no lines
+        let mut editor = Editor::new();
+        editor.begin_block_select();
+        assert!(editor.copy().unwrap().is_empty());
+        assert!(editor.cut_block((0, 0), (0, 0)).is_empty());
+
+        // cutting every line leaves an empty one
+        let mut editor = Editor::from("a\nb");
+        editor.cut_lines(0, 1);
+        assert_eq!(editor.buffer.len(), 1);
+        assert_eq!(editor.to_string(), "");
+    }
+
+    #[test]
+    fn test_editor_is_selected() {
+        let mut editor = Editor::from("abc\nde\nfgh");
+
+        // the anchor is after the cursor
+        editor.set_cursor((1, 2));
+        editor.begin_select();
+        editor.set_cursor((2, 0));
+
+        assert!(!editor.is_selected((1, 0)));
+        assert!(editor.is_selected((2, 0)));
+        assert!(editor.is_selected((3, 0)));
+        assert!(!editor.is_selected((4, 0)));
+        assert!(editor.is_selected((0, 1)));
+        assert!(editor.is_selected((0, 2)));
+        assert!(!editor.is_selected((1, 2)));
+
+        editor.clear_selection();
+        assert!(!editor.is_selected((2, 0)));
+    }
+
+    #[test]
+    fn test_editor_retab() {
+        let mut editor = Editor::from("\t\tx\n     y = 1;\t// z\n\t  w");
+
+        editor.set_cursor((2, 0));
+        assert_eq!(editor.expand_tabs(2), 2);
+        assert_eq!(editor.to_string(), "    x\n     y = 1;\t// z\n    w");
+        assert_eq!(editor.cursor, Vector2(4, 0));
+
+        // only the indentation is turned back into tabs
+        editor.set_cursor((6, 1));
+        assert_eq!(editor.unexpand_tabs(2), 3);
+        assert_eq!(editor.to_string(), "\t\tx\n\t\t y = 1;\t// z\n\t\tw");
+        assert_eq!(editor.cursor, Vector2(4, 1));
+    }
+
+    #[test]
+    fn test_editor_line_positions() {
+        let mut editor = Editor::from("  \tabc\n");
+        editor.set_cursor((5, 0));
+        assert_eq!(
+            editor.move_cursor_to(Position::FirstNonBlank),
+            Vector2(3, 0)
+        );
+        assert_eq!(editor.move_cursor_to(Position::LineEnd), Vector2(6, 0));
+    }
+
+    #[test]
+    fn test_editor_matching_bracket() {
+        let editor = Editor::from("fn a(b: [u8; 2]) {\n    (b)\n}\n(");
+        assert_eq!(editor.matching_bracket(Vector2(4, 0)), Some(Vector2(15, 0)));
+        assert_eq!(editor.matching_bracket(Vector2(15, 0)), Some(Vector2(4, 0)));
+        assert_eq!(editor.matching_bracket(Vector2(0, 0)), Some(Vector2(15, 0)));
+        assert_eq!(editor.matching_bracket(Vector2(17, 0)), Some(Vector2(0, 2)));
+        assert_eq!(editor.matching_bracket(Vector2(0, 2)), Some(Vector2(17, 0)));
+        assert_eq!(editor.matching_bracket(Vector2(0, 3)), None);
+        assert_eq!(editor.matching_bracket(Vector2(8, 1)), None);
+    }
+
+    #[test]
+    fn test_editor_paragraphs() {
+        let mut editor = Editor::from("a\nb\n\n\nc\n\nd");
+        assert_eq!(
+            editor.move_cursor_to(Position::NextParagraph),
+            Vector2(0, 2)
+        );
+        assert_eq!(
+            editor.move_cursor_to(Position::NextParagraph),
+            Vector2(0, 5)
+        );
+        assert_eq!(
+            editor.move_cursor_to(Position::NextParagraph),
+            Vector2(0, 6)
+        );
+        assert_eq!(
+            editor.move_cursor_to(Position::NextParagraph),
+            Vector2(0, 6)
+        );
+
+        assert_eq!(
+            editor.move_cursor_to(Position::PreviousParagraph),
+            Vector2(0, 5)
+        );
+        assert_eq!(
+            editor.move_cursor_to(Position::PreviousParagraph),
+            Vector2(0, 3)
+        );
+        assert_eq!(
+            editor.move_cursor_to(Position::PreviousParagraph),
+            Vector2(0, 0)
+        );
+    }
+
+    #[test]
+    fn test_editor_adjust_number() {
+        let mut editor = Editor::from("x = 9;\ny = -3 + 1\n");
+        assert!(editor.adjust_number(1));
+        assert_eq!(editor.to_string(), "x = 10;\ny = -3 + 1\n");
+        assert_eq!(editor.cursor, Vector2(5, 0));
+
+        editor.set_cursor((5, 1));
+        assert!(editor.adjust_number(10));
+        assert_eq!(editor.to_string(), "x = 10;\ny = 7 + 1\n");
+        assert_eq!(editor.cursor, Vector2(4, 1));
+
+        editor.set_cursor((6, 0));
+        assert!(!editor.adjust_number(1));
+    }
+
+    #[test]
+    fn test_editor_toggle_case() {
+        let mut editor = Editor::from("aBß\nxyz\n");
+        assert!(editor.change_case_at_cursor(Case::Toggle));
+        assert!(editor.change_case_at_cursor(Case::Toggle));
+        assert!(editor.change_case_at_cursor(Case::Toggle));
+        assert!(!editor.change_case_at_cursor(Case::Toggle));
+        assert_eq!(editor.to_string(), "Abß\nxyz\n");
+        assert_eq!(editor.cursor, Vector2(3, 0));
+
+        editor.set_cursor((1, 0));
+        editor.begin_select();
+        editor.set_cursor((2, 1));
+        editor.change_case_selection(Case::Toggle);
+        assert_eq!(editor.to_string(), "ABß\nXYz\n");
+        assert!(!editor.is_selecting());
+
+        editor.set_cursor((0, 1));
+        editor.begin_line_select();
+        editor.change_case_selection(Case::Lower);
+        assert_eq!(editor.to_string(), "ABß\nxyz\n");
+        editor.set_cursor((2, 0));
+        assert!(editor.change_case_at_cursor(Case::Upper));
+        assert_eq!(editor.to_string(), "ABß\nxyz\n");
+
+        // there is nothing to change in an empty buffer
+        let mut editor = Editor::new();
+        editor.begin_select();
+        editor.change_case_selection(Case::Upper);
+        assert_eq!(editor.to_string(), "");
+    }
+
+    #[test]
+    fn test_editor_replace_char() {
+        let mut editor = Editor::from("abc\n");
+        editor.set_cursor((1, 0));
+        assert!(editor.replace_char('x'));
+        assert_eq!(editor.to_string(), "axc\n");
+        assert_eq!(editor.cursor, Vector2(1, 0));
+
+        editor.set_cursor((3, 0));
+        assert!(!editor.replace_char('x'));
+        assert_eq!(editor.to_string(), "axc\n");
+    }
+
+    #[test]
+    fn test_editor_delete_to() {
+        let mut editor = Editor::from("hello world\nnext\n");
+        editor.set_cursor((5, 0));
+
+        let deleted: String = editor
+            .delete_to(Position::LineEnd)
+            .iter()
+            .map(|x| x.char)
+            .collect();
+        assert_eq!(deleted, " world");
+        assert_eq!(editor.to_string(), "hello\nnext\n");
+        assert_eq!(editor.cursor, Vector2(5, 0));
+
+        assert!(editor.delete_to(Position::LineEnd).is_empty());
+        assert_eq!(editor.to_string(), "hello\nnext\n");
+    }
+
+    #[test]
+    fn test_editor_join_line() {
+        let mut editor = Editor::from("a  \n   b\n\nc\nd\n");
+        assert!(editor.join_line());
+        assert_eq!(editor.to_string(), "a b\n\nc\nd\n");
+        assert_eq!(editor.cursor, Vector2(1, 0));
+
+        editor.begin_select();
+        editor.set_cursor((0, 2));
+        assert!(editor.join_line());
+        assert_eq!(editor.to_string(), "a b c\nd\n");
+        assert_eq!(editor.cursor, Vector2(3, 0));
+        assert!(!editor.is_selecting());
+
+        editor.set_cursor((0, 2));
+        assert!(!editor.join_line());
+    }
+
+    #[test]
+    fn test_editor_duplicate_line() {
+        let mut editor = Editor::from("a\nbc\nd\n");
+        editor.set_cursor((1, 1));
+
+        editor.duplicate_line();
+        assert_eq!(editor.to_string(), "a\nbc\nbc\nd\n");
+        assert_eq!(editor.cursor, Vector2(1, 2));
+
+        editor.set_cursor((0, 0));
+        editor.begin_select();
+        editor.set_cursor((1, 1));
+        editor.duplicate_line();
+        assert_eq!(editor.to_string(), "a\nbc\na\nbc\nbc\nd\n");
+        assert_eq!(
+            editor.selection_range(),
+            Some((Vector2(0, 2), Vector2(1, 3)))
+        );
+    }
+
+    #[test]
+    fn test_editor_move_lines() {
+        let mut editor = Editor::from("a\nb\nc\nd\n");
+        editor.set_cursor((0, 1));
+
+        assert!(editor.move_lines(1..=2, 1));
+        assert_eq!(editor.to_string(), "a\nd\nb\nc\n");
+        assert_eq!(editor.cursor, Vector2(0, 2));
+
+        assert!(!editor.move_lines(2..=3, 1));
+        assert!(!editor.move_lines(0..=0, -1));
+
+        assert!(editor.move_lines(2..=3, -2));
+        assert_eq!(editor.to_string(), "b\nc\na\nd\n");
+        assert_eq!(editor.cursor, Vector2(0, 0));
+    }
+
+    #[test]
+    fn test_editor_indent_lines() {
+        let mut editor = Editor::from("a\n\n  b\n\tc\n");
+        editor.set_cursor((1, 2));
+
+        editor.indent_lines(0..=3, 4);
+        assert_eq!(editor.to_string(), "    a\n\n      b\n    \tc\n");
+        assert_eq!(editor.cursor, Vector2(5, 2));
+
+        editor.dedent_lines(0..=3, 4);
+        editor.dedent_lines(0..=3, 4);
+        assert_eq!(editor.to_string(), "a\n\nb\nc\n");
+        assert_eq!(editor.cursor, Vector2(0, 2));
+    }
+
+    #[test]
+    fn test_editor_indent() {
+        let mut editor = Editor::from("    \tfn main() {\n      x\n");
+        assert_eq!(editor.current_indent(), "    \t");
+
+        editor.set_cursor((5, 0));
+        assert_eq!(editor.backspace_width(4), 1);
+        editor.set_cursor((4, 0));
+        assert_eq!(editor.backspace_width(4), 4);
+        editor.set_cursor((6, 0));
+        assert_eq!(editor.backspace_width(4), 1);
+
+        editor.set_cursor((6, 1));
+        assert_eq!(editor.current_indent(), "      ");
+        assert_eq!(editor.backspace_width(4), 2);
+        editor.set_cursor((7, 1));
+        assert_eq!(editor.backspace_width(4), 1);
+    }
+
+    #[test]
+    fn test_editor_tab_stop_width() {
+        let mut editor = Editor::from("ab\tc\n");
+        assert_eq!(editor.tab_stop_width(4), 4);
+        editor.set_cursor((1, 0));
+        assert_eq!(editor.tab_stop_width(4), 3);
+        editor.set_cursor((3, 0));
+        assert_eq!(editor.tab_stop_width(4), 4);
+        editor.set_cursor((4, 0));
+        assert_eq!(editor.tab_stop_width(4), 3);
+
+        // a new file has no lines yet
+        assert_eq!(Editor::new().tab_stop_width(4), 4);
+        assert_eq!(Editor::from("").tab_stop_width(4), 4);
+    }
+
+    #[test]
+    fn test_editor_search() {
+        let mut editor = Editor::from("abab\nxab\n");
+        assert_eq!(editor.search("ab", (0, 0), false), Some(Vector2(0, 0)));
+        assert_eq!(editor.search("ab", (1, 0), false), Some(Vector2(2, 0)));
+        assert_eq!(editor.search("ab", (3, 0), false), Some(Vector2(1, 1)));
+        assert_eq!(editor.search("ab", (2, 1), false), None);
+        assert_eq!(editor.search("", (0, 0), false), None);
+        assert_eq!(editor.search("XA", (0, 0), false), None);
+        assert_eq!(editor.search("XA", (0, 0), true), Some(Vector2(0, 1)));
+
+        editor.replace_range(Vector2(1, 1), Vector2(3, 1), "yz!");
+        assert_eq!(editor.to_string(), "abab\nxyz!\n");
+        assert_eq!(editor.cursor, Vector2(4, 1));
+    }
+
+    #[test]
+    fn test_editor_search_regex() {
+        let editor = Editor::from("a1 b2\nc3\n");
+        let pattern = Pattern::new(r"[a-z]\d").unwrap();
+        assert_eq!(
+            editor.search_regex(&pattern, (1, 0), false),
+            Some(Vector2(3, 0))
+        );
+        assert_eq!(
+            editor.search_regex(&pattern, (4, 0), false),
+            Some(Vector2(0, 1))
+        );
+        assert_eq!(editor.search_regex(&pattern, (1, 1), false), None);
+
+        assert_eq!(
+            editor.search_regex(&pattern, (0, 1), true),
+            Some(Vector2(3, 0))
+        );
+        assert_eq!(
+            editor.search_regex(&pattern, (3, 0), true),
+            Some(Vector2(0, 0))
+        );
+        assert_eq!(editor.search_regex(&pattern, (0, 0), true), None);
+    }
+
+    #[test]
+    fn test_editor_search_wrapping() {
+        let editor = Editor::from("a1 b2\nc3\n");
+        let pattern = Pattern::new(r"\d").unwrap();
+        assert_eq!(
+            editor.search_wrapping(&pattern, (2, 0), false, true),
+            Some((Vector2(4, 0), false))
+        );
+        assert_eq!(
+            editor.search_wrapping(&pattern, (2, 1), false, true),
+            Some((Vector2(1, 0), true))
+        );
+        assert_eq!(editor.search_wrapping(&pattern, (2, 1), false, false), None);
+        assert_eq!(
+            editor.search_wrapping(&pattern, (0, 0), true, true),
+            Some((Vector2(1, 1), true))
+        );
+    }
+
+    #[test]
+    fn test_editor_search_all() {
+        let editor = Editor::from("ab ab\nb\nab");
+        let pattern = Pattern::new("ab").unwrap();
+        assert_eq!(
+            editor.search_all(&pattern),
+            vec![Vector2(0, 0), Vector2(3, 0), Vector2(0, 2)]
+        );
+    }
+
+    #[test]
+    fn test_editor_find_matches() {
+        let editor = Editor::from("ab ab\nb\nab");
+        let pattern = Pattern::new("ab|x*").unwrap();
+        assert_eq!(
+            editor.find_matches(&pattern, 0..2),
+            vec![
+                (Vector2(0, 0), Vector2(2, 0)),
+                (Vector2(3, 0), Vector2(5, 0))
+            ]
+        );
+        assert_eq!(
+            editor.find_matches(&pattern, 2..10),
+            vec![(Vector2(0, 2), Vector2(2, 2))]
+        );
+    }
+
+    #[test]
+    fn test_editor_replace_all() {
+        let mut editor = Editor::from("a a\nb\na\n");
+        editor.set_cursor((2, 0));
+        assert_eq!(editor.replace_all("a", "xyz", false), 2);
+        assert_eq!(editor.to_string(), "xyz a\nb\nxyz\n");
+
+        assert_eq!(editor.replace_all("xyz", "", true), 2);
+        assert_eq!(editor.to_string(), " a\nb\n\n");
+        assert_eq!(editor.cursor, Vector2(2, 0));
+        assert_eq!(editor.replace_all("", "a", true), 0);
+    }
+
+    #[test]
+    fn test_editor_sort_lines() {
+        let mut editor = Editor::from("keep\nbanana\nApple\ncherry\n");
+
+        editor.sort_lines(1..=4, false, false);
+        assert_eq!(editor.to_string(), "keep\nApple\nbanana\ncherry\n");
+        assert_eq!(editor.cursor, Vector2(0, 1));
+
+        editor.sort_lines(1..=3, true, true);
+        assert_eq!(editor.to_string(), "keep\ncherry\nbanana\nApple\n");
+
+        // an empty buffer, or one left with a single empty line, has nothing to sort
+        let mut editor = Editor::new();
+        editor.sort_lines(0..=0, false, true);
+        assert_eq!(editor.to_string(), "");
+        let mut editor = Editor::from("a");
+        editor.cut_lines(0, 0);
+        editor.sort_lines(0..=0, false, true);
+        assert_eq!(editor.to_string(), "");
+    }
+
+    #[test]
+    fn test_editor_word_object() {
+        let mut editor = Editor::from("foo.bar  baz\n");
+        editor.set_cursor((1, 0));
+        assert_eq!(
+            editor.word_object(false),
+            Some((Vector2(0, 0), Vector2(3, 0)))
+        );
+        assert_eq!(
+            editor.word_object(true),
+            Some((Vector2(0, 0), Vector2(3, 0)))
+        );
+
+        editor.set_cursor((5, 0));
+        assert_eq!(
+            editor.word_object(false),
+            Some((Vector2(4, 0), Vector2(7, 0)))
+        );
+        assert_eq!(
+            editor.word_object(true),
+            Some((Vector2(4, 0), Vector2(9, 0)))
+        );
+
+        editor.set_cursor((7, 0));
+        assert_eq!(
+            editor.word_object(false),
+            Some((Vector2(7, 0), Vector2(9, 0)))
+        );
+        assert_eq!(
+            editor.word_object(true),
+            Some((Vector2(7, 0), Vector2(12, 0)))
+        );
+
+        // without whitespace after the word the whitespace before it is included
+        editor.set_cursor((10, 0));
+        assert_eq!(
+            editor.word_object(true),
+            Some((Vector2(7, 0), Vector2(12, 0)))
+        );
+
+        editor.set_cursor((0, 1));
+        assert_eq!(editor.word_object(false), None);
+    }
+
+    #[test]
+    fn test_editor_delimiter_object() {
+        let mut editor = Editor::from("f(a, (b), \"c\") \"d\"\n{\n  x\n}");
+        editor.set_cursor((2, 0));
+        assert_eq!(
+            editor.delimiter_object('(', ')', false),
+            Some((Vector2(2, 0), Vector2(13, 0)))
+        );
+
+        // nested pairs are skipped, and the cursor may be on a delimiter
+        editor.set_cursor((8, 0));
+        assert_eq!(
+            editor.delimiter_object('(', ')', true),
+            Some((Vector2(1, 0), Vector2(14, 0)))
+        );
+        editor.set_cursor((7, 0));
+        assert_eq!(
+            editor.delimiter_object('(', ')', true),
+            Some((Vector2(5, 0), Vector2(8, 0)))
+        );
+
+        editor.set_cursor((0, 0));
+        assert_eq!(
+            editor.delimiter_object('"', '"', false),
+            Some((Vector2(11, 0), Vector2(12, 0)))
+        );
+        editor.set_cursor((11, 0));
+        assert_eq!(
+            editor.delimiter_object('"', '"', true),
+            Some((Vector2(10, 0), Vector2(13, 0)))
+        );
+        editor.set_cursor((17, 0));
+        assert_eq!(
+            editor.delimiter_object('"', '"', false),
+            Some((Vector2(16, 0), Vector2(17, 0)))
+        );
+
+        editor.set_cursor((1, 2));
+        assert_eq!(
+            editor.delimiter_object('{', '}', false),
+            Some((Vector2(1, 1), Vector2(0, 3)))
+        );
+        assert_eq!(editor.delimiter_object('[', ']', false), None);
+    }
+
+    #[test]
+    fn test_editor_surround() {
+        let mut editor = Editor::from("one two\nthree");
+        editor.set_cursor((6, 0));
+        editor.surround(Vector2(4, 0), Vector2(7, 0), '(', ')');
+        assert_eq!(editor.to_string(), "one (two)\nthree");
+        assert_eq!(editor.cursor, Vector2(4, 0));
+
+        editor.surround(Vector2(8, 0), Vector2(3, 1), '"', '"');
+        assert_eq!(editor.to_string(), "one (two\")\nthr\"ee");
+        assert!(editor.is_modified());
+    }
+
+    #[test]
+    fn test_editor_toggle_comment() {
+        let mut editor = Editor::from("fn a() {\n    b();\n\n}");
+        editor.set_cursor((4, 1));
+        assert!(editor.toggle_comment(0..=3, "// "));
+        assert_eq!(editor.to_string(), "// fn a() {\n    // b();\n\n// }");
+        assert_eq!(editor.cursor, Vector2(7, 1));
+
+        assert!(!editor.toggle_comment(0..=3, "// "));
+        assert_eq!(editor.to_string(), "fn a() {\n    b();\n\n}");
+        assert_eq!(editor.cursor, Vector2(4, 1));
+
+        // lines commented without the space are uncommented, partly commented lines are commented
+        let mut editor = Editor::from("//a\n# b\nc");
+        assert!(!editor.toggle_comment(0..=0, "// "));
+        assert_eq!(editor.to_string(), "a\n# b\nc");
+        assert!(editor.toggle_comment(1..=2, "# "));
+        assert_eq!(editor.to_string(), "a\n# # b\n# c");
+    }
+
+    #[test]
+    fn test_editor_marks() {
+        let mut editor = Editor::from("a\nbc\nd\ne");
+        editor.set_cursor((1, 1));
+        editor.set_mark('a');
+        editor.set_cursor((0, 3));
+        editor.set_mark('b');
+        assert_eq!(editor.mark('c'), None);
+
+        // marks move with lines inserted and removed above them
+        editor.write_at((0, 0), '\n');
+        assert_eq!(editor.mark('a'), Some(Vector2(1, 2)));
+        editor.write_at((1, 2), '\n');
+        assert_eq!(editor.mark('a'), Some(Vector2(0, 3)));
+        assert_eq!(editor.mark('b'), Some(Vector2(0, 5)));
+        editor.delete_at((0, 3));
+        assert_eq!(editor.mark('a'), Some(Vector2(1, 2)));
+        editor.cut_lines(0, 0);
+        assert_eq!(editor.mark('b'), Some(Vector2(0, 3)));
+        editor.duplicate_line();
+        assert_eq!(editor.mark('b'), Some(Vector2(0, 4)));
+
+        // marks on removed lines are removed
+        editor.cut_lines(2, 2);
+        assert_eq!(editor.mark('a'), None);
+        assert_eq!(editor.mark('b'), Some(Vector2(0, 3)));
+    }
+
+    #[test]
+    fn test_editor_delete_chars() {
+        let mut editor = Editor::from("abcdef\ng");
+        editor.set_cursor((1, 0));
+        let text = |cells: Vec<CharCel>| cells.iter().map(|x| x.char).collect::<String>();
+        assert_eq!(text(editor.delete_chars(1, true)), "b");
+        assert_eq!(editor.to_string(), "acdef\ng");
+        assert_eq!(editor.cursor, Vector2(1, 0));
+
+        // counts stop at the ends of the line
+        assert_eq!(text(editor.delete_chars(10, true)), "cdef");
+        assert_eq!(editor.to_string(), "a\ng");
+        assert_eq!(text(editor.delete_chars(5, false)), "a");
+        assert_eq!(editor.to_string(), "\ng");
+        assert!(editor.delete_chars(1, false).is_empty());
+
+        let mut editor = Editor::from("e\u{301}x");
+        assert_eq!(text(editor.delete_chars(1, true)), "e\u{301}");
+    }
+
+    #[test]
+    fn test_editor_counts() {
+        let editor = Editor::from("one two\n\n  three\tfour\n");
+        assert_eq!(editor.line_count(), 4);
+        assert_eq!(editor.word_count(), 4);
+        assert_eq!(editor.char_count(), 22);
+        assert_eq!(count_words(&editor.copy_range((2, 0), (4, 2))), 3);
+        assert_eq!(Editor::new().char_count(), 0);
+    }
+
+    #[test]
+    fn test_editor_reflow() {
+        let mut editor = Editor::from("  one two\n    three four five six\n\nseven eight nine\n");
+        editor.set_cursor((3, 1));
+        assert_eq!(editor.paragraph_lines(), 0..=1);
+
+        assert_eq!(editor.reflow(editor.paragraph_lines(), 14), 3);
+        assert_eq!(
+            editor.to_string(),
+            "  one two\n    three four\n    five six\n\nseven eight nine\n"
+        );
+        assert_eq!(editor.cursor, Vector2(0, 0));
+
+        assert_eq!(editor.reflow(0..=4, 80), 3);
+        assert_eq!(
+            editor.to_string(),
+            "  one two three four five six\n\nseven eight nine\n"
+        );
+        editor.reflow(2..=2, 3);
+        assert_eq!(
+            editor.to_string(),
+            "  one two three four five six\n\nseven\neight\nnine\n"
+        );
+    }
+
+    #[test]
+    fn test_editor_grapheme_clusters() {
+        let mut editor = Editor::from("ae\u{301}b 🇯🇵🇫🇷 👍🏽");
+        editor.move_cursor((2, 0));
+        assert_eq!(editor.cursor, Vector2(3, 0));
+        editor.move_cursor((-1, 0));
+        assert_eq!(editor.cursor, Vector2(1, 0));
+
+        editor.set_cursor((6, 0));
+        editor.move_cursor((1, 0));
+        assert_eq!(editor.cursor, Vector2(7, 0));
+        editor.move_cursor((1, 0));
+        assert_eq!(editor.cursor, Vector2(9, 0));
+
+        editor.move_cursor((9999, 0));
+        editor.delete();
+        assert_eq!(editor.to_string(), "ae\u{301}b 🇯🇵🇫🇷 ");
+        editor.set_cursor((3, 0));
+        editor.delete();
+        assert_eq!(editor.to_string(), "ab 🇯🇵🇫🇷 ");
+        assert_eq!(editor.cursor, Vector2(1, 0));
+    }
+
+    #[test]
+    fn test_editor_line_ending() {
+        let editor = Editor::from("a\r\nb\r\n");
+        assert_eq!(editor.line_ending, LineEnding::CrLf);
+        assert_eq!(editor.to_string(), "a\r\nb\r\n");
+
+        let editor = Editor::from("a\r\nb\nc\n");
+        assert_eq!(editor.line_ending, LineEnding::Lf);
+        assert_eq!(editor.to_string(), "a\nb\nc\n");
+        assert_eq!(Editor::from("a").line_ending, LineEnding::Lf);
+    }
+
+    #[test]
+    fn test_editor_bom() {
+        let mut editor = Editor::from("\u{feff}ab");
+        assert!(editor.bom);
+        assert_eq!(editor.get_cell((0, 0)).unwrap().char, 'a');
+        assert_eq!(editor.to_string(), "\u{feff}ab");
+
+        editor.bom = false;
+        assert_eq!(editor.to_string(), "ab");
+    }
+
+    #[test]
+    fn test_editor_modified() {
+        let mut editor = Editor::from("ab\ncd");
+        assert!(!editor.is_modified());
+
+        editor.dedent_lines(0..=1, 4);
+        editor.replace_all("x", "y", true);
+        editor.set_cursor((0, 0));
+        editor.delete();
+        assert!(!editor.is_modified());
+
+        editor.write('z');
+        assert!(editor.is_modified());
+        editor.set_modified(false);
+        editor.join_line();
+        assert!(editor.is_modified());
+    }
+
     // ensure that cut and copy produce the same results
     #[test]
     fn test_editor_copy_range() {
@@ -627,6 +3026,43 @@ mod test {
         assert_eq!(editor.to_string(), "");
     }
 
+    // the buffer is a line per row, so an edit in the middle of a large file only moves
+    // the rest of its line, or the list of lines when a line is split.
+    // the timings depend on the machine, so it only runs with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_editor_large_file_insert() {
+        use std::time::{Duration, Instant};
+
+        // 200,000 lines of 50 bytes is 10MB
+        let line = "abcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvw\n";
+        let mut editor = Editor::from(line.repeat(200_000));
+        editor.set_cursor((25, 100_000));
+
+        let start = Instant::now();
+        for _ in 0..1000 {
+            editor.write('x');
+        }
+        let chars = start.elapsed();
+        let start = Instant::now();
+        for _ in 0..1000 {
+            editor.write('\n');
+        }
+        let lines = start.elapsed();
+
+        assert_eq!(editor.line_count(), 201_001);
+        assert!(
+            chars < Duration::from_millis(100),
+            "1000 characters took {:?}",
+            chars
+        );
+        assert!(
+            lines < Duration::from_secs(1),
+            "1000 new lines took {:?}",
+            lines
+        );
+    }
+
     #[test]
     // TODO : Consider removing carriage returns within the editor function
     // make sure the output is equal to the input
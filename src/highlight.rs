@@ -0,0 +1,114 @@
+//! colors spans of text for the renderer
+
+use crossterm::style::Color;
+use std::ops::Range;
+use std::path::Path;
+use std::rc::Rc;
+
+/// A highlighter picks the colors of the text on a line
+pub trait Highlighter {
+    /// Return the foreground color of spans of `line`.
+    /// ranges are indices of characters rather than bytes, later spans take priority
+    fn style(&self, line: &str, line_no: i32) -> Vec<(Range<usize>, Color)>;
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+/// Highlights the keywords, string literals and line comments of rust source code
+pub struct RustHighlighter {
+    pub keyword: Color,
+    pub string: Color,
+    pub comment: Color,
+}
+
+impl Default for RustHighlighter {
+    fn default() -> Self {
+        Self {
+            keyword: Color::Magenta,
+            string: Color::Green,
+            comment: Color::DarkGrey,
+        }
+    }
+}
+
+impl Highlighter for RustHighlighter {
+    fn style(&self, line: &str, _line_no: i32) -> Vec<(Range<usize>, Color)> {
+        let chars: Vec<char> = line.chars().collect();
+        let mut spans = Vec::new();
+
+        let mut i = 0;
+        while i < chars.len() {
+            let start = i;
+
+            if chars[i] == '/' && chars.get(i + 1) == Some(&'/') {
+                // the comment continues to the end of the line
+                spans.push((start..chars.len(), self.comment));
+                break;
+            } else if chars[i] == '"' {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    // skip over escaped characters
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                spans.push((start..i, self.string));
+            } else if chars[i].is_alphanumeric() || chars[i] == '_' {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                if RUST_KEYWORDS.contains(&word.as_str()) {
+                    spans.push((start..i, self.keyword));
+                }
+            } else {
+                i += 1;
+            }
+        }
+
+        spans
+    }
+}
+
+/// return the highlighter for the file at `path` by its extension,
+/// or none for file types it has no highlighter for
+pub fn for_path(path: &Path) -> Option<Rc<dyn Highlighter>> {
+    match path.extension()?.to_str()? {
+        "rs" => Some(Rc::new(RustHighlighter::default())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_highlighter_for_path() {
+        assert!(for_path(Path::new("src/main.rs")).is_some());
+        assert!(for_path(Path::new("notes.txt")).is_none());
+        assert!(for_path(Path::new("README")).is_none());
+    }
+
+    #[test]
+    fn test_rust_highlighter() {
+        let highlighter = RustHighlighter::default();
+        let spans = highlighter.style(r#"let fnord = "a \" fn"; // fn"#, 0);
+
+        assert_eq!(
+            spans,
+            vec![
+                (0..3, highlighter.keyword),
+                (12..21, highlighter.string),
+                (23..28, highlighter.comment),
+            ]
+        );
+    }
+}
@@ -0,0 +1,288 @@
+//! the commands keys run, which can be rebound in the config file.
+//! keys are named as in the help text, `M-x` for alt, `^x` for ctrl, `F1` and `up`, `tab`, ...
+use crossterm::input::KeyEvent;
+use std::collections::HashMap;
+
+/// define the `Command` enum along with each command's name and default key
+macro_rules! commands {
+    ($($(#[$doc:meta])* $variant:ident => $name:expr, $key:expr;)*) => {
+        /// An action run by a key, keys that are not bound to a command are typed
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum Command {
+            $($(#[$doc])* $variant,)*
+        }
+
+        impl Command {
+            /// every command, in the order of the help text
+            pub const ALL: &'static [Command] = &[$(Command::$variant,)*];
+
+            /// the name the command is bound by in the config file
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Command::$variant => $name,)*
+                }
+            }
+
+            /// the key the command is bound to unless the config file changes it
+            pub fn default_key(self) -> KeyEvent {
+                use KeyEvent::*;
+                match self {
+                    $(Command::$variant => $key,)*
+                }
+            }
+        }
+    };
+}
+
+commands! {
+    MoveDown => "move_down", Down;
+    MoveUp => "move_up", Up;
+    MoveRight => "move_right", Right;
+    MoveLeft => "move_left", Left;
+    ScrollDown => "scroll_down", CtrlDown;
+    ScrollUp => "scroll_up", CtrlUp;
+    ScrollRight => "scroll_right", CtrlRight;
+    ScrollLeft => "scroll_left", CtrlLeft;
+    Help => "help", F(1);
+    Refresh => "refresh", F(5);
+    Quit => "quit", Ctrl('c');
+    Save => "save", Ctrl('s');
+    /// move the cursor into the middle of the view
+    CursorToView => "cursor_to_view", Ctrl('a');
+    CenterView => "center_view", Ctrl('l');
+    Stats => "stats", Ctrl('g');
+    HalfPageDown => "half_page_down", Ctrl('d');
+    HalfPageUp => "half_page_up", Ctrl('u');
+    /// wait for z, t or b to place the cursor's line in the view
+    AlignView => "align_view", Alt('z');
+    FirstLine => "first_line", Alt('g');
+    /// move to the last line, or the line given by the count
+    GoToLine => "go_to_line", Alt('G');
+    NextParagraph => "next_paragraph", Alt('}');
+    PreviousParagraph => "previous_paragraph", Alt('{');
+    MatchingBracket => "matching_bracket", Alt('%');
+    LineStart => "line_start", Alt('0');
+    SetMark => "set_mark", Alt('m');
+    JumpToMark => "jump_to_mark", Alt('`');
+    JumpBack => "jump_back", Ctrl('p');
+    JumpForward => "jump_forward", Ctrl('n');
+    /// start recording keys into a register, or stop recording
+    Record => "record", Alt('Q');
+    Replay => "replay", Alt('@');
+    FindChar => "find_char", Alt('f');
+    FindCharBackward => "find_char_backward", Alt('F');
+    TillChar => "till_char", Alt('t');
+    TillCharBackward => "till_char_backward", Alt('T');
+    RepeatFind => "repeat_find", Alt(';');
+    RepeatFindReverse => "repeat_find_reverse", Alt(',');
+    RepeatChange => "repeat_change", Alt('.');
+    Select => "select", Alt('v');
+    SelectLines => "select_lines", Alt('V');
+    SelectBlock => "select_block", Ctrl('b');
+    /// clear the selection and the search highlight
+    Escape => "escape", Esc;
+    Increment => "increment", Alt('+');
+    Decrement => "decrement", Alt('-');
+    ExCommand => "command", Alt(':');
+    Search => "search", Alt('/');
+    SearchBackward => "search_backward", Alt('?');
+    ToggleIgnoreCase => "ignore_case", Alt('i');
+    Replace => "replace", Alt('R');
+    ToggleCase => "toggle_case", Alt('~');
+    Lowercase => "lowercase", Alt('u');
+    Uppercase => "uppercase", Alt('U');
+    ReplaceChar => "replace_char", Alt('r');
+    DeleteChar => "delete_char", Alt('x');
+    DeleteCharBefore => "delete_char_before", Alt('X');
+    DeleteToEnd => "delete_to_end", Alt('D');
+    ChangeToEnd => "change_to_end", Alt('C');
+    JoinLines => "join_lines", Alt('J');
+    DuplicateLines => "duplicate_lines", Alt('d');
+    MoveLinesDown => "move_lines_down", Alt('j');
+    MoveLinesUp => "move_lines_up", Alt('k');
+    Indent => "indent", Alt('>');
+    Dedent => "dedent", Alt('<');
+    Reflow => "reflow", Alt('q');
+    Comment => "comment", Alt('#');
+    SortLines => "sort_lines", Alt('s');
+    SortLinesReverse => "sort_lines_reverse", Alt('S');
+    /// wait for an operator and the text object it acts on
+    TextObject => "text_object", Alt('o');
+    YankLines => "yank_lines", Alt('Y');
+    Surround => "surround", Alt('a');
+    Copy => "copy", Alt('c');
+    Paste => "paste", Ctrl('v');
+    Register => "register", Alt('"');
+    Backspace => "backspace", Backspace;
+    NewLine => "new_line", Enter;
+    /// insert spaces to the next tab stop, or indent the selection
+    Tab => "tab", Tab;
+    Outdent => "outdent", BackTab;
+    /// move to the first non-blank character, then to the start of the line
+    Home => "home", Home;
+    End => "end", End;
+    FirstNonBlank => "first_non_blank", Alt('I');
+    LineEnd => "line_end", Alt('A');
+    LineNumbers => "line_numbers", Alt('n');
+    HighlightLine => "highlight_line", Alt('h');
+    ShowWhitespace => "show_whitespace", Alt('w');
+    Wrap => "wrap", Alt('W');
+    ExpandTabs => "expand_tabs", Alt('e');
+    UnexpandTabs => "unexpand_tabs", Alt('E');
+    OpenFile => "open_file", Ctrl('o');
+}
+
+impl Command {
+    /// return the command called `name`
+    pub fn named(name: &str) -> Option<Command> {
+        Command::ALL.iter().copied().find(|x| x.name() == name)
+    }
+
+    /// whether the command changes the text of the buffer
+    pub fn is_edit(self) -> bool {
+        use Command::*;
+        matches!(
+            self,
+            RepeatChange
+                | Increment
+                | Decrement
+                | Replace
+                | ToggleCase
+                | Lowercase
+                | Uppercase
+                | ReplaceChar
+                | DeleteChar
+                | DeleteCharBefore
+                | DeleteToEnd
+                | ChangeToEnd
+                | JoinLines
+                | DuplicateLines
+                | MoveLinesDown
+                | MoveLinesUp
+                | Indent
+                | Dedent
+                | Reflow
+                | Comment
+                | SortLines
+                | SortLinesReverse
+                | Surround
+                | Paste
+                | Backspace
+                | NewLine
+                | Tab
+                | Outdent
+                | ExpandTabs
+                | UnexpandTabs
+        )
+    }
+}
+
+/// The command each key runs
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyMap {
+    bindings: HashMap<KeyEvent, Command>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let bindings = Command::ALL
+            .iter()
+            .map(|&command| (command.default_key(), command))
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl KeyMap {
+    /// the command `key` runs
+    pub fn get(&self, key: KeyEvent) -> Option<Command> {
+        self.bindings.get(&key).copied()
+    }
+
+    /// make `key` run `command`, or nothing so it is typed when `command` is None
+    pub fn bind(&mut self, key: KeyEvent, command: Option<Command>) {
+        match command {
+            Some(command) => self.bindings.insert(key, command),
+            None => self.bindings.remove(&key),
+        };
+    }
+}
+
+/// return the key called `name`, such as `M-x`, `^s`, `^up`, `F1` or `tab`
+pub fn parse_key(name: &str) -> Option<KeyEvent> {
+    use KeyEvent::*;
+
+    let single = |text: &str| {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+    if let Some(rest) = name.strip_prefix("M-") {
+        return single(rest).map(Alt);
+    }
+    if let Some(rest) = name.strip_prefix('^').or_else(|| name.strip_prefix("C-")) {
+        return match rest.to_lowercase().as_str() {
+            "up" => Some(CtrlUp),
+            "down" => Some(CtrlDown),
+            "left" => Some(CtrlLeft),
+            "right" => Some(CtrlRight),
+            _ => single(rest).map(|c| Ctrl(c.to_ascii_lowercase())),
+        };
+    }
+    if let Some(c) = single(name) {
+        return Some(Char(c));
+    }
+    match name.to_lowercase().as_str() {
+        "up" => Some(Up),
+        "down" => Some(Down),
+        "left" => Some(Left),
+        "right" => Some(Right),
+        "home" => Some(Home),
+        "end" => Some(End),
+        "pageup" => Some(PageUp),
+        "pagedown" => Some(PageDown),
+        "tab" => Some(Tab),
+        "backtab" | "shift-tab" => Some(BackTab),
+        "backspace" => Some(Backspace),
+        "delete" => Some(Delete),
+        "insert" => Some(Insert),
+        "enter" => Some(Enter),
+        "esc" => Some(Esc),
+        name => name
+            .strip_prefix('f')
+            .and_then(|x| x.parse().ok())
+            .filter(|&x| (1..=12).contains(&x))
+            .map(F),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_keymap_defaults() {
+        let keymap = KeyMap::default();
+        assert_eq!(keymap.bindings.len(), Command::ALL.len());
+        assert_eq!(keymap.get(KeyEvent::Alt('q')), Some(Command::Reflow));
+        assert_eq!(keymap.get(KeyEvent::Char('q')), None);
+        assert_eq!(Command::named("sort_lines"), Some(Command::SortLines));
+        assert_eq!(Command::named("sort"), None);
+        assert!(Command::Paste.is_edit());
+        assert!(!Command::Copy.is_edit());
+    }
+
+    #[test]
+    fn test_keymap_parse_key() {
+        assert_eq!(parse_key("M-x"), Some(KeyEvent::Alt('x')));
+        assert_eq!(parse_key("^S"), Some(KeyEvent::Ctrl('s')));
+        assert_eq!(parse_key("C-up"), Some(KeyEvent::CtrlUp));
+        assert_eq!(parse_key("F12"), Some(KeyEvent::F(12)));
+        assert_eq!(parse_key("Tab"), Some(KeyEvent::Tab));
+        assert_eq!(parse_key("x"), Some(KeyEvent::Char('x')));
+        assert_eq!(parse_key("M-xy"), None);
+        assert_eq!(parse_key("F13"), None);
+    }
+}
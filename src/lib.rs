@@ -1,4 +1,9 @@
-pub mod application;
-pub mod clipboard;
-pub mod editor;
-pub mod renderer;
+pub mod application;
+pub mod clipboard;
+pub mod config;
+pub mod editor;
+pub mod highlight;
+pub mod keymap;
+pub mod pattern;
+pub mod positions;
+pub mod renderer;
@@ -1,15 +1,71 @@
 use rust_ed::application::Application;
 use rust_ed::clipboard::OsClipboard;
+use rust_ed::config::{self, Config};
 use rust_ed::editor::Editor;
+use rust_ed::positions;
 use std::error::Error;
+use std::ffi::OsString;
+use std::io::{IsTerminal, Read};
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let mut app = Application::new(
-        Editor::from(include_str!("../resources/sample_text.txt")),
-        OsClipboard::new()?,
-    );
+    // -R opens the file read-only
+    let mut args: Vec<OsString> = std::env::args_os().skip(1).collect();
+    let read_only = args.iter().any(|x| x == "-R");
+    args.retain(|x| x != "-R");
+
+    let mut app = match args.into_iter().next() {
+        Some(path) => Application::open(path, OsClipboard::new()?)?,
+        None if !std::io::stdin().is_terminal() => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text)?;
+            reopen_stdin()?;
+
+            // piped text has no file, so it is only saved once one is named with :w
+            let mut app = Application::new(Editor::from(text), OsClipboard::new()?);
+            app.log = "read from stdin, save with :w <path>".to_string();
+            app
+        }
+        None => Application::new(
+            Editor::from(include_str!("../resources/sample_text.txt")),
+            OsClipboard::new()?,
+        ),
+    };
+
+    if let Some(path) = config::default_path() {
+        let (config, warnings) = Config::load(&path);
+        app.apply_config(&config);
+        // a mistake in the config is shown rather than stopping the editor from starting
+        if !warnings.is_empty() {
+            app.log = warnings.join("; ");
+        }
+    }
+
+    app.read_only = read_only;
+
+    // land where the cursor was left the last time the file was edited
+    app.positions_file = positions::default_path();
+    app.update_view_size()?;
+    app.restore_position();
 
     app.run()?;
 
     Ok(())
 }
+
+/// point stdin at the terminal after reading piped text from it,
+/// raw mode is set on stdin so keys can be read one at a time
+#[cfg(unix)]
+fn reopen_stdin() -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let tty = std::fs::File::open("/dev/tty")?;
+    if unsafe { libc::dup2(tty.as_raw_fd(), libc::STDIN_FILENO) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn reopen_stdin() -> std::io::Result<()> {
+    Ok(())
+}
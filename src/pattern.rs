@@ -0,0 +1,535 @@
+//! a small regular expression matcher for searching the buffer.
+//! supports literals, `.`, `[]` classes, `\d \w \s` and their negations,
+//! the anchors `^ $`, groups with `|` alternatives and the quantifiers `* + ?`.
+//! patterns are compiled to a program run over the text in one pass without recursion,
+//! so a long line can not overflow the stack
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// a `(` without a matching `)`
+    UnclosedGroup,
+    /// a `)` without a matching `(`
+    UnmatchedParen,
+    /// a `[` without a matching `]`
+    UnclosedClass,
+    /// a quantifier with nothing before it
+    NothingToRepeat,
+    /// a `\` at the end of the pattern
+    TrailingBackslash,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid pattern: {}",
+            match self {
+                Error::UnclosedGroup => "unclosed group",
+                Error::UnmatchedParen => "unmatched )",
+                Error::UnclosedClass => "unclosed character class",
+                Error::NothingToRepeat => "nothing to repeat",
+                Error::TrailingBackslash => "trailing backslash",
+            }
+        )
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// a set of characters matched by a class such as `[a-z]` or `\w`
+#[derive(Clone, Debug, PartialEq)]
+struct Class {
+    ranges: Vec<(char, char)>,
+    negated: bool,
+}
+
+impl Class {
+    fn contains(&self, c: char) -> bool {
+        self.ranges.iter().any(|&(a, b)| c >= a && c <= b) != self.negated
+    }
+
+    /// the class for an escape such as `\d`, if it is one
+    fn from_escape(c: char) -> Option<Class> {
+        let ranges = match c.to_ascii_lowercase() {
+            'd' => vec![('0', '9')],
+            'w' => vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')],
+            's' => vec![(' ', ' '), ('\t', '\t'), ('\r', '\r'), ('\n', '\n')],
+            _ => return None,
+        };
+        Some(Class {
+            ranges,
+            negated: c.is_ascii_uppercase(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    /// alternatives, the first that matches is used
+    Group(Vec<Vec<Node>>),
+    /// a node repeated at least `min` and at most `max` times, as many times as possible
+    Repeat(Box<Node>, usize, Option<usize>),
+}
+
+/// a step of the program a pattern is compiled to
+#[derive(Clone, Debug, PartialEq)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    /// continue at both instructions, preferring the first
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+/// A compiled regular expression
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    program: Vec<Inst>,
+    /// letters match regardless of their case
+    pub ignore_case: bool,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Pattern> {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut parser = Parser { chars, i: 0 };
+        let alternatives = parser.parse_alternatives()?;
+        if parser.i < parser.chars.len() {
+            return Err(Error::UnmatchedParen);
+        }
+        let mut program = Vec::new();
+        compile_alternatives(&mut program, &alternatives);
+        program.push(Inst::Match);
+        Ok(Pattern {
+            program,
+            ignore_case: false,
+        })
+    }
+
+    /// set whether letters match regardless of their case
+    pub fn ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    /// Return the first match in `text` starting at or after `start`.
+    /// the range is of character indices
+    pub fn find_at(&self, text: &[char], start: usize) -> Option<Range<usize>> {
+        let matcher = Matcher {
+            program: &self.program,
+            text,
+            ignore_case: self.ignore_case,
+        };
+        matcher.find(start)
+    }
+
+    /// Return every match in `text` that does not overlap an earlier one
+    pub fn find_all(&self, text: &[char]) -> Vec<Range<usize>> {
+        let mut matches = Vec::new();
+        let mut start = 0;
+        while let Some(found) = self.find_at(text, start) {
+            // an empty match still moves the search along
+            start = found.end.max(found.start + 1);
+            matches.push(found);
+        }
+        matches
+    }
+}
+
+/// escape the characters in `text` that have a meaning in a pattern,
+/// so the pattern matches `text` literally
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.^$|()[]*+?".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// lowercase a character for comparisons that ignore case
+pub fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// add the instructions for `nodes` to `program`
+fn compile(program: &mut Vec<Inst>, nodes: &[Node]) {
+    for node in nodes {
+        compile_node(program, node);
+    }
+}
+
+/// add the instructions for alternatives to `program`, the first that matches is used
+fn compile_alternatives(program: &mut Vec<Inst>, alternatives: &[Vec<Node>]) {
+    let mut jumps = Vec::new();
+    for (i, nodes) in alternatives.iter().enumerate() {
+        if i + 1 == alternatives.len() {
+            compile(program, nodes);
+            break;
+        }
+        let split = program.len();
+        program.push(Inst::Split(split + 1, 0));
+        compile(program, nodes);
+        jumps.push(program.len());
+        program.push(Inst::Jump(0));
+        program[split] = Inst::Split(split + 1, program.len());
+    }
+    // every alternative continues after the last one
+    for jump in jumps {
+        program[jump] = Inst::Jump(program.len());
+    }
+}
+
+fn compile_node(program: &mut Vec<Inst>, node: &Node) {
+    match node {
+        Node::Char(c) => program.push(Inst::Char(*c)),
+        Node::Any => program.push(Inst::Any),
+        Node::Class(class) => program.push(Inst::Class(class.clone())),
+        Node::Start => program.push(Inst::Start),
+        Node::End => program.push(Inst::End),
+        Node::Group(alternatives) => compile_alternatives(program, alternatives),
+        Node::Repeat(node, min, max) => {
+            for _ in 0..*min {
+                compile_node(program, node);
+            }
+            match max {
+                // try to match once more before trying to stop
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile_node(program, node);
+                    program.push(Inst::Jump(split));
+                    program[split] = Inst::Split(split + 1, program.len());
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Inst::Split(0, 0));
+                        compile_node(program, node);
+                    }
+                    for split in splits {
+                        program[split] = Inst::Split(split + 1, program.len());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// the threads running the program at a position in the text, in order of priority
+struct Threads {
+    /// the instruction each thread waits at and where its match began
+    list: Vec<(usize, usize)>,
+    /// whether an instruction has a thread, so each runs once per position
+    added: Vec<bool>,
+    stack: Vec<usize>,
+}
+
+impl Threads {
+    fn new(len: usize) -> Threads {
+        Threads {
+            list: Vec::new(),
+            added: vec![false; len],
+            stack: Vec::new(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.list.clear();
+        self.added.iter_mut().for_each(|x| *x = false);
+    }
+}
+
+/// the text being matched against
+struct Matcher<'a> {
+    program: &'a [Inst],
+    text: &'a [char],
+    ignore_case: bool,
+}
+
+impl<'a> Matcher<'a> {
+    /// whether the character at `i` is matched by `is_match`
+    fn char_at(&self, i: usize, is_match: impl Fn(char) -> bool) -> bool {
+        match self.text.get(i) {
+            Some(&c) if self.ignore_case => {
+                is_match(c) || is_match(fold_case(c)) || c.to_uppercase().any(&is_match)
+            }
+            Some(&c) => is_match(c),
+            None => false,
+        }
+    }
+
+    /// return the first match starting at or after `start`. every start is tried at once,
+    /// a thread from an earlier start or a preferred path outranks the ones after it
+    fn find(&self, start: usize) -> Option<Range<usize>> {
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        let mut found = None;
+        for i in start..=self.text.len() {
+            if found.is_none() {
+                self.add_thread(&mut current, 0, i, i);
+            } else if current.list.is_empty() {
+                break;
+            }
+            for &(pc, begin) in &current.list {
+                if self.program[pc] == Inst::Match {
+                    // the threads after this one are less preferred
+                    found = Some(begin..i);
+                    break;
+                }
+                if self.consumes(&self.program[pc], i) {
+                    self.add_thread(&mut next, pc + 1, begin, i + 1);
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+        }
+        found
+    }
+
+    /// add a thread at `pc` to `threads`, following jumps, splits and anchors at `i`
+    /// so only the instructions that match a character or the whole pattern are added
+    fn add_thread(&self, threads: &mut Threads, pc: usize, begin: usize, i: usize) {
+        threads.stack.push(pc);
+        while let Some(pc) = threads.stack.pop() {
+            if std::mem::replace(&mut threads.added[pc], true) {
+                continue;
+            }
+            match self.program[pc] {
+                Inst::Jump(to) => threads.stack.push(to),
+                Inst::Split(first, second) => {
+                    threads.stack.push(second);
+                    threads.stack.push(first);
+                }
+                Inst::Start if i == 0 => threads.stack.push(pc + 1),
+                Inst::End if i == self.text.len() => threads.stack.push(pc + 1),
+                Inst::Start | Inst::End => {}
+                _ => threads.list.push((pc, begin)),
+            }
+        }
+    }
+
+    /// whether `inst` matches the character at `i`
+    fn consumes(&self, inst: &Inst, i: usize) -> bool {
+        match inst {
+            Inst::Char(c) if self.ignore_case => self.char_at(i, |x| fold_case(x) == fold_case(*c)),
+            Inst::Char(c) => self.char_at(i, |x| x == *c),
+            Inst::Any => self.char_at(i, |_| true),
+            Inst::Class(class) => self.char_at(i, |x| class.contains(x)),
+            _ => false,
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    i: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.i).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.i += 1;
+        c
+    }
+
+    fn parse_alternatives(&mut self) -> Result<Vec<Vec<Node>>> {
+        let mut alternatives = vec![self.parse_sequence()?];
+        while self.peek() == Some('|') {
+            self.i += 1;
+            alternatives.push(self.parse_sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn parse_sequence(&mut self) -> Result<Vec<Node>> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            self.i += 1;
+
+            let (min, max) = match c {
+                '*' => (0, None),
+                '+' => (1, None),
+                '?' => (0, Some(1)),
+                _ => {
+                    nodes.push(self.parse_atom(c)?);
+                    continue;
+                }
+            };
+            let node = match nodes.pop() {
+                Some(Node::Repeat(..)) | None => return Err(Error::NothingToRepeat),
+                Some(node) => node,
+            };
+            nodes.push(Node::Repeat(Box::new(node), min, max));
+        }
+        Ok(nodes)
+    }
+
+    fn parse_atom(&mut self, c: char) -> Result<Node> {
+        Ok(match c {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '(' => {
+                let alternatives = self.parse_alternatives()?;
+                if self.next() != Some(')') {
+                    return Err(Error::UnclosedGroup);
+                }
+                Node::Group(alternatives)
+            }
+            '[' => Node::Class(self.parse_class()?),
+            '\\' => {
+                let c = self.next().ok_or(Error::TrailingBackslash)?;
+                match Class::from_escape(c) {
+                    Some(class) => Node::Class(class),
+                    None => Node::Char(escaped(c)),
+                }
+            }
+            c => Node::Char(c),
+        })
+    }
+
+    fn parse_class(&mut self) -> Result<Class> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.i += 1;
+        }
+
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = match self.next() {
+                // a `]` at the start of the class is taken literally
+                Some(']') if !first => break,
+                Some('\\') => {
+                    let c = self.next().ok_or(Error::UnclosedClass)?;
+                    if let Some(class) = Class::from_escape(c) {
+                        ranges.extend(class.ranges);
+                        first = false;
+                        continue;
+                    }
+                    escaped(c)
+                }
+                Some(c) => c,
+                None => return Err(Error::UnclosedClass),
+            };
+            first = false;
+
+            // a `-` between two characters makes a range
+            if self.peek() == Some('-')
+                && matches!(self.chars.get(self.i + 1), Some(&x) if x != ']')
+            {
+                self.i += 1;
+                let end = self.next().ok_or(Error::UnclosedClass)?;
+                ranges.push((c, end));
+            } else {
+                ranges.push((c, c));
+            }
+        }
+        Ok(Class { ranges, negated })
+    }
+}
+
+/// the character written by an escape such as `\t`
+fn escaped(c: char) -> char {
+    match c {
+        't' => '\t',
+        'n' => '\n',
+        'r' => '\r',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn find(pattern: &str, text: &str) -> Option<Range<usize>> {
+        let text: Vec<char> = text.chars().collect();
+        Pattern::new(pattern).unwrap().find_at(&text, 0)
+    }
+
+    #[test]
+    fn test_pattern_find() {
+        assert_eq!(find("b+", "abbbc"), Some(1..4));
+        assert_eq!(find("a.c", "xabc"), Some(1..4));
+        assert_eq!(find("^a", "ba"), None);
+        assert_eq!(find("a$", "aba"), Some(2..3));
+        assert_eq!(find("colou?r", "color"), Some(0..5));
+        assert_eq!(find("(cat|dog)s*$", "hotdogss"), Some(3..8));
+        assert_eq!(find("[a-c]+", "xxcab!"), Some(2..5));
+        assert_eq!(find("[^a-c]", "cab!"), Some(3..4));
+        assert_eq!(find(r"\d+\.\d", "v 10.5"), Some(2..6));
+        assert_eq!(find(r"\w+\s", "fn main"), Some(0..3));
+        assert_eq!(find("a.*b", "a1b2b3"), Some(0..5));
+        assert_eq!(find("x*", "abc"), Some(0..0));
+        assert_eq!(find("(a*)*b", "aab"), Some(0..3));
+    }
+
+    #[test]
+    fn test_pattern_long_line() {
+        let mut text: Vec<char> = "ab cd ".repeat(200_000).chars().collect();
+        let find = |pattern: &str, text: &[char]| Pattern::new(pattern).unwrap().find_at(text, 0);
+        assert_eq!(find(".*z", &text), None);
+        assert_eq!(find("(ab|cd| )*z", &text), None);
+
+        text.push('z');
+        let all = Some(0..text.len());
+        assert_eq!(find(".*z", &text), all);
+        assert_eq!(find("a.*z", &text), all);
+        assert_eq!(find("[a-d ]*z", &text), all);
+        assert_eq!(find("(ab|cd| )*z", &text), all);
+    }
+
+    #[test]
+    fn test_pattern_ignore_case() {
+        let text: Vec<char> = "Fn MAIN".chars().collect();
+        let pattern = Pattern::new("main|[f]n").unwrap();
+        assert_eq!(pattern.find_at(&text, 0), None);
+        assert_eq!(pattern.ignore_case(true).find_at(&text, 0), Some(0..2));
+    }
+
+    #[test]
+    fn test_pattern_find_all() {
+        let text: Vec<char> = "a1 b22 c".chars().collect();
+        let pattern = Pattern::new(r"\d+").unwrap();
+        assert_eq!(pattern.find_all(&text), vec![1..2, 4..6]);
+    }
+
+    #[test]
+    fn test_pattern_escape() {
+        assert_eq!(find(&escape("a.(b)*"), "ab a.(b)*"), Some(3..9));
+    }
+
+    #[test]
+    fn test_pattern_errors() {
+        assert_eq!(Pattern::new("(a"), Err(Error::UnclosedGroup));
+        assert_eq!(Pattern::new("a)"), Err(Error::UnmatchedParen));
+        assert_eq!(Pattern::new("[a"), Err(Error::UnclosedClass));
+        assert_eq!(Pattern::new("*a"), Err(Error::NothingToRepeat));
+        assert_eq!(Pattern::new("a\\"), Err(Error::TrailingBackslash));
+    }
+}
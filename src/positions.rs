@@ -0,0 +1,99 @@
+//! remembers where the cursor was left in each file between runs.
+//! the state file has a line for each file, its cursor line and column followed by its path
+use crate::editor::Vector2;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// the number of files whose positions are kept, the least recently used are forgotten first
+pub const MAX_POSITIONS: usize = 100;
+
+/// return the state file positions are kept in, `~/.rust-ed/positions`
+pub fn default_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(".rust-ed").join("positions"))
+}
+
+/// The last cursor location in each file, the most recently used first
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Positions {
+    entries: Vec<(PathBuf, Vector2)>,
+}
+
+impl Positions {
+    /// read the positions stored at `path`, a missing or unreadable file has none
+    pub fn load(path: &Path) -> Positions {
+        std::fs::read_to_string(path)
+            .map(|text| Positions::parse(&text))
+            .unwrap_or_default()
+    }
+
+    /// parse the lines of a state file, skipping any that are malformed
+    pub fn parse(text: &str) -> Positions {
+        let entries = text
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, ' ');
+                let y = parts.next()?.parse().ok()?;
+                let x = parts.next()?.parse().ok()?;
+                let path = parts.next().filter(|x| !x.is_empty())?;
+                Some((PathBuf::from(path), Vector2(x, y)))
+            })
+            .take(MAX_POSITIONS)
+            .collect();
+        Positions { entries }
+    }
+
+    /// the cursor location last stored for `file`
+    pub fn get(&self, file: &Path) -> Option<Vector2> {
+        self.entries
+            .iter()
+            .find(|(path, _)| path == file)
+            .map(|&(_, location)| location)
+    }
+
+    /// store the cursor location for `file` as the most recently used
+    pub fn set(&mut self, file: PathBuf, location: Vector2) {
+        self.entries.retain(|(path, _)| *path != file);
+        self.entries.insert(0, (file, location));
+        self.entries.truncate(MAX_POSITIONS);
+    }
+
+    /// write the positions to `path`, creating the directory it is in
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for Positions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (path, location) in &self.entries {
+            writeln!(f, "{} {} {}", location.y(), location.x(), path.display())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_positions() {
+        let mut positions = Positions::parse("3 4 /a b.txt\nbad line\n1 0 /c.rs\n");
+        assert_eq!(positions.get(Path::new("/a b.txt")), Some(Vector2(4, 3)));
+        assert_eq!(positions.get(Path::new("/c.rs")), Some(Vector2(0, 1)));
+        assert_eq!(positions.get(Path::new("/d.rs")), None);
+
+        positions.set(PathBuf::from("/c.rs"), Vector2(2, 5));
+        assert_eq!(positions.to_string(), "5 2 /c.rs\n3 4 /a b.txt\n");
+
+        for i in 0..MAX_POSITIONS {
+            positions.set(PathBuf::from(format!("/{}", i)), Vector2(0, 0));
+        }
+        assert_eq!(positions.get(Path::new("/c.rs")), None);
+        assert_eq!(positions.get(Path::new("/0")), Some(Vector2(0, 0)));
+    }
+}
@@ -1,11 +1,118 @@
 //! handles rendering an editor state
 
 use crate::editor::{Editor, Vector2};
+use crate::highlight::Highlighter;
+
+use crossterm::style::{Color, ResetColor, SetBackgroundColor, SetForegroundColor};
+use std::rc::Rc;
+
+/// the numbers drawn in the line number gutter
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineNumberMode {
+    /// no gutter is drawn
+    None,
+    /// every line shows its line number
+    Absolute,
+    /// every line shows its distance from the cursor's line
+    Relative,
+    /// the cursor's line shows its line number, others show their distance from it
+    Hybrid,
+}
+
+impl LineNumberMode {
+    /// return the mode following this one, wrapping back around to `None`
+    pub fn next(self) -> Self {
+        match self {
+            LineNumberMode::None => LineNumberMode::Absolute,
+            LineNumberMode::Absolute => LineNumberMode::Relative,
+            LineNumberMode::Relative => LineNumberMode::Hybrid,
+            LineNumberMode::Hybrid => LineNumberMode::None,
+        }
+    }
+}
+
+/// the colors used to draw the editor
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    /// the text and background colors of the status line
+    pub status_fg: Color,
+    pub status_bg: Color,
+    /// the background color of the cursor's line when it is highlighted
+    pub current_line: Color,
+    /// the background color of selected text
+    pub selection: Color,
+    /// the background color of text matching the last search
+    pub search_match: Color,
+    /// the background color of trailing whitespace when whitespace is drawn
+    pub trailing_whitespace: Color,
+    /// the background color of the color column
+    pub color_column: Color,
+    /// the color of the ~ drawn on rows past the end of the buffer
+    pub end_of_buffer: Color,
+}
+
+impl Theme {
+    /// colors for terminals with a dark background
+    pub fn dark() -> Self {
+        Self {
+            status_fg: Color::Reset,
+            status_bg: Color::Reset,
+            current_line: Color::DarkGrey,
+            selection: Color::DarkBlue,
+            search_match: Color::DarkYellow,
+            trailing_whitespace: Color::DarkRed,
+            color_column: Color::DarkGrey,
+            end_of_buffer: Color::DarkGrey,
+        }
+    }
+
+    /// colors for terminals with a light background
+    pub fn light() -> Self {
+        Self {
+            status_fg: Color::White,
+            status_bg: Color::DarkGrey,
+            current_line: Color::Grey,
+            selection: Color::Cyan,
+            search_match: Color::Yellow,
+            trailing_whitespace: Color::Red,
+            color_column: Color::Grey,
+            end_of_buffer: Color::Grey,
+        }
+    }
+
+    /// return the theme called `name`
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
 
 /// contains parameters for rendering
 #[derive(Clone, Copy, Debug)]
 pub struct RenderOpts {
     pub view: Rect,
+    /// the line numbers drawn in a gutter on the left side of the view
+    pub line_numbers: LineNumberMode,
+    /// paint the background of the cursor's line
+    pub highlight_current_line: bool,
+    /// draw spaces as `·` and tabs as `→`, and paint the background of trailing whitespace
+    pub render_whitespace: bool,
+    pub theme: Theme,
+    /// the number of columns a tab is drawn across
+    pub tab_width: usize,
+    /// draw lines longer than the view across several rows instead of scrolling sideways
+    pub wrap: bool,
+    /// a column, counted from 1, whose background is tinted on every row as a ruler
+    pub color_column: Option<i32>,
 }
 
 impl Default for RenderOpts {
@@ -16,8 +123,146 @@ impl Default for RenderOpts {
                 width: 0,
                 height: 0,
             },
+            line_numbers: LineNumberMode::None,
+            highlight_current_line: false,
+            render_whitespace: false,
+            theme: Theme::default(),
+            tab_width: 4,
+            wrap: false,
+            color_column: None,
+        }
+    }
+}
+
+impl RenderOpts {
+    /// return the number of columns taken up by the line number gutter.
+    /// the gutter fits the largest line number followed by a space
+    pub fn gutter_width(&self, editor: &Editor) -> i32 {
+        if self.line_numbers != LineNumberMode::None {
+            editor.line_count().max(1).to_string().len() as i32 + 1
+        } else {
+            0
+        }
+    }
+
+    /// return the number of columns a character is drawn across
+    pub fn char_width(&self, c: char) -> i32 {
+        if c == '\t' {
+            self.tab_width as i32
+        } else if is_wide(c) {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// return the column a location in the editor is drawn at, counting from the start of its line.
+    /// locations past the end of the line are one column apart
+    pub fn screen_column(&self, editor: &Editor, location: Vector2) -> i32 {
+        let row = match editor.get_row(location.y()) {
+            Some(row) if location.x() > 0 => row,
+            _ => return location.x(),
+        };
+
+        let x = location.x() as usize;
+        let drawn: i32 = row
+            .iter()
+            .take(x)
+            .map(|cell| self.char_width(cell.char))
+            .sum();
+        drawn + x.saturating_sub(row.len()) as i32
+    }
+
+    /// return the drawn column each row of `line` starts at when it is wrapped to `width` columns.
+    /// rows break after the last whitespace that fits on them, or wherever the text reaches
+    /// `width` when there is none
+    pub fn wrap_points(&self, editor: &Editor, line: i32, width: i32) -> Vec<i32> {
+        let mut starts = vec![0];
+        let row = match editor.get_row(line) {
+            Some(row) => row,
+            None => return starts,
+        };
+
+        let (mut row_start, mut drawn) = (0, 0);
+        // the column after the last whitespace on the current row
+        let mut last_break = None;
+        for cell in row {
+            let char_width = self.char_width(cell.char);
+            if drawn + char_width - row_start > width && drawn > row_start {
+                row_start = match last_break {
+                    Some(x) if drawn + char_width - x <= width => x,
+                    _ => drawn,
+                };
+                starts.push(row_start);
+                last_break = None;
+            }
+            drawn += char_width;
+            if cell.char.is_whitespace() {
+                last_break = Some(drawn);
+            }
+        }
+        starts
+    }
+
+    /// return the row and column relative to the top left of the text area that `location`
+    /// is drawn at when lines are wrapped, if it is on the screen
+    pub fn wrapped_position(&self, editor: &Editor, location: Vector2) -> Option<Vector2> {
+        let width = self.view.width - self.gutter_width(editor);
+        if location.y() < self.view.y() {
+            return None;
+        }
+
+        let rows: i32 = (self.view.y()..location.y())
+            .map(|y| self.wrap_points(editor, y, width).len() as i32)
+            .sum();
+        let column = self.screen_column(editor, location);
+        let starts = self.wrap_points(editor, location.y(), width);
+        let i = starts.iter().rposition(|&x| x <= column).unwrap_or(0);
+
+        let position = Vector2((column - starts[i]).min(width - 1), rows + i as i32);
+        if position.y() < self.view.height {
+            Some(position)
+        } else {
+            None
         }
     }
+
+    /// return the location in the editor drawn at a row and column relative to the top left
+    /// of the text area when lines are wrapped
+    pub fn wrapped_location(&self, editor: &Editor, position: Vector2) -> Vector2 {
+        let width = self.view.width - self.gutter_width(editor);
+        let mut rows = position.y();
+        let mut y = self.view.y();
+        loop {
+            let starts = self.wrap_points(editor, y, width);
+            if rows < starts.len() as i32 || y as usize >= editor.line_count() {
+                let start = starts[rows.clamp(0, starts.len() as i32 - 1) as usize];
+                let end = starts.get(rows as usize + 1).map_or(i32::MAX, |&x| x - 1);
+                let column = (start + position.x()).min(end);
+                return Vector2(self.buffer_column(editor, y, column), y);
+            }
+            rows -= starts.len() as i32;
+            y += 1;
+        }
+    }
+
+    /// return the column in the editor drawn at `column` on a line,
+    /// columns in the middle of a tab belong to the tab
+    pub fn buffer_column(&self, editor: &Editor, line: i32, column: i32) -> i32 {
+        let row = match editor.get_row(line) {
+            Some(row) if column > 0 => row,
+            _ => return column,
+        };
+
+        let mut drawn = 0;
+        for (i, cell) in row.iter().enumerate() {
+            drawn += self.char_width(cell.char);
+            if drawn > column {
+                return i as i32;
+            }
+        }
+        row.len() as i32 + column - drawn
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -51,11 +296,99 @@ pub trait Renderer {
     fn render(&self, editor: &Editor, opts: RenderOpts) -> Self::Output;
 }
 
+/// return true for characters drawn across two columns of the terminal,
+/// such as CJK ideographs, hangul, full width forms and emoji
+fn is_wide(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD)
+}
+
+/// return the lines of `frame` which differ from the line drawn on the same row of `previous`,
+/// along with their row
+pub fn changed_lines<'a>(previous: &[String], frame: &'a str) -> Vec<(usize, &'a str)> {
+    frame
+        .lines()
+        .enumerate()
+        .filter(|&(y, line)| previous.get(y).map(String::as_str) != Some(line))
+        .collect()
+}
+
+/// draw the lines of `text` from line `scroll` on inside a border centered in `view`,
+/// cutting off the lines and rows which do not fit
+///
+/// # Returns
+/// the area of the screen covered by the box and its rows
+pub fn text_box(text: &str, scroll: usize, view: Rect) -> (Rect, Vec<String>) {
+    let lines: Vec<&str> = text.lines().collect();
+    let longest = lines.iter().map(|x| x.chars().count()).max().unwrap_or(0) as i32;
+    let width = (longest + 4).min(view.width).max(4);
+    let height = (lines.len() as i32 + 2).min(view.height).max(2);
+    let inner = (width - 4) as usize;
+
+    let border = "─".repeat(inner + 2);
+    let mut rows = vec![format!("┌{}┐", border)];
+    for line in lines.iter().skip(scroll).take(height as usize - 2) {
+        let line: String = line.chars().take(inner).collect();
+        rows.push(format!("│ {:inner$} │", line, inner = inner));
+    }
+    while rows.len() < height as usize - 1 {
+        rows.push(format!("│ {:inner$} │", "", inner = inner));
+    }
+    rows.push(format!("└{}┘", border));
+
+    let area = Rect {
+        location: Vector2(
+            view.x() + (view.width - width).max(0) / 2,
+            view.y() + (view.height - height).max(0) / 2,
+        ),
+        width,
+        height,
+    };
+    (area, rows)
+}
+
+/// the colors a cell is drawn with, `None` leaves the terminal's default color
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct CellStyle {
+    fg: Option<Color>,
+    bg: Option<Color>,
+}
+
+impl CellStyle {
+    /// push the escape codes switching from the current style to this one
+    fn push_to(&self, screen: &mut String) {
+        screen.push_str(&ResetColor.to_string());
+        if let Some(fg) = self.fg {
+            screen.push_str(&SetForegroundColor(fg).to_string());
+        }
+        if let Some(bg) = self.bg {
+            screen.push_str(&SetBackgroundColor(bg).to_string());
+        }
+    }
+}
+
 /// renders an editor state to a string
 pub struct StringRenderer {
     // only render a particular line in the editor
     pub line_hint: Option<i32>,
     pub break_on_line_end: bool,
+    // colors the text of each line
+    pub highlighter: Option<Rc<dyn Highlighter>>,
+    // the start and end of the search matches to paint, each on a single line
+    pub search_matches: Vec<(Vector2, Vector2)>,
 }
 
 impl StringRenderer {
@@ -63,6 +396,8 @@ impl StringRenderer {
         Self {
             line_hint: None,
             break_on_line_end: false,
+            highlighter: None,
+            search_matches: Vec::new(),
         }
     }
 
@@ -70,8 +405,29 @@ impl StringRenderer {
         Self {
             line_hint: Some(line),
             break_on_line_end: false,
+            highlighter: None,
+            search_matches: Vec::new(),
         }
     }
+
+    /// return the foreground color of every cell in a row
+    fn highlight_row(&self, editor: &Editor, y: i32) -> Vec<Option<Color>> {
+        let row = match editor.get_row(y) {
+            Some(row) if y >= 0 => row,
+            _ => return Vec::new(),
+        };
+
+        let mut colors = vec![None; row.len()];
+        if let Some(highlighter) = &self.highlighter {
+            let line: String = row.iter().map(|x| x.char).collect();
+            for (range, color) in highlighter.style(&line, y) {
+                for i in range.start..range.end.min(colors.len()) {
+                    colors[i] = Some(color);
+                }
+            }
+        }
+        colors
+    }
 }
 
 impl Renderer for StringRenderer {
@@ -96,18 +452,157 @@ impl Renderer for StringRenderer {
         };
 
         let x2 = opts.view.location.x();
+        let gutter = opts.gutter_width(editor);
+        let cursor_line = editor.cursor_pos().y();
+
+        let text_width = width - gutter;
+        let wrap = opts.wrap && self.line_hint.is_none();
+
+        let mut rows = 0;
+        let mut y = y2;
+        while rows < height {
+            // rows past the end of the buffer are marked with a ~
+            if y >= editor.line_count() as i32 && width > 0 {
+                screen.push_str(&format!(
+                    "{}~{}{}\n",
+                    SetForegroundColor(opts.theme.end_of_buffer),
+                    ResetColor,
+                    " ".repeat(width as usize - 1)
+                ));
+                rows += 1;
+                y += 1;
+                continue;
+            }
+
+            let colors = self.highlight_row(editor, y);
+            let line_color = if opts.highlight_current_line && y == cursor_line {
+                Some(opts.theme.current_line)
+            } else {
+                None
+            };
+            let row = editor.get_row(y).map_or(&[][..], |row| &row[..]);
 
-        for y in y2..y2 + height {
-            for x in x2..x2 + width {
-                if let Some(cell) = editor.get_cell((x, y)) {
-                    screen.push(cell.char);
-                } else if self.break_on_line_end && x > 0 {
+            // the column where whitespace at the end of the line begins
+            let trailing_whitespace = row
+                .iter()
+                .rposition(|x| !x.char.is_whitespace())
+                .map_or(0, |x| x + 1) as i32;
+
+            // the position in the line and the character drawn in each column,
+            // and whether the column is covered by a wide character
+            let mut columns = Vec::with_capacity(row.len());
+            for (i, cell) in row.iter().enumerate() {
+                let char_width = opts.char_width(cell.char);
+                for column in 0..char_width {
+                    columns.push((
+                        i as i32,
+                        match cell.char {
+                            '\t' if opts.render_whitespace && column == 0 => '→',
+                            '\t' => ' ',
+                            ' ' if opts.render_whitespace => '·',
+                            x => x,
+                        },
+                        cell.char != '\t' && char_width > 1,
+                    ));
+                }
+            }
+
+            // the drawn column each row of the line starts at, wrapped lines span several rows
+            let row_starts = if wrap {
+                opts.wrap_points(editor, y, text_width)
+            } else {
+                vec![x2]
+            };
+
+            for (i, &row_start) in row_starts.iter().enumerate() {
+                if rows == height {
                     break;
-                } else {
-                    screen.push(' ');
                 }
+                rows += 1;
+
+                if gutter > 0 {
+                    if i == 0 && y >= 0 && (y as usize) < editor.line_count() {
+                        let number = match opts.line_numbers {
+                            LineNumberMode::Relative => (y - cursor_line).abs(),
+                            LineNumberMode::Hybrid if y != cursor_line => (y - cursor_line).abs(),
+                            _ => y + 1,
+                        };
+                        screen.push_str(&format!("{:>1$} ", number, gutter as usize - 1));
+                    } else {
+                        screen.push_str(&" ".repeat(gutter as usize));
+                    }
+                }
+
+                let mut style = CellStyle::default();
+                let end = row_start + text_width;
+                // the text of a wrapped row ends where the next row begins
+                let text_end = row_starts.get(i + 1).copied().unwrap_or(end);
+                // a wide character covers the column after it
+                let mut covered = false;
+
+                for x in row_start..end {
+                    if std::mem::replace(&mut covered, false) {
+                        continue;
+                    }
+                    let (column, c) = match columns.get(x as usize) {
+                        // columns drawn on the next row are left blank, with no position in the line
+                        _ if x >= text_end => (-1, None),
+                        Some(&(column, c, true)) if x >= 0 => {
+                            // wide characters cut by the edges of the view are drawn as a space
+                            let first = x == 0 || columns[x as usize - 1].0 != column;
+                            if first && x + 1 < end {
+                                covered = true;
+                                (column, Some(c))
+                            } else {
+                                (column, Some(' '))
+                            }
+                        }
+                        Some(&(column, c, false)) if x >= 0 => (column, Some(c)),
+                        _ if x >= 0 => (x - columns.len() as i32 + row.len() as i32, None),
+                        _ => (x, None),
+                    };
+
+                    let is_match = |&(start, end): &(Vector2, Vector2)| {
+                        y == start.y() && column >= start.x() && column < end.x()
+                    };
+                    // wrapped rows count columns from the edge of the view
+                    let ruler = if wrap { x - row_start } else { x };
+                    let bg = if editor.is_selected((column, y)) {
+                        Some(opts.theme.selection)
+                    } else if self.search_matches.iter().any(is_match) {
+                        Some(opts.theme.search_match)
+                    } else if opts.render_whitespace && c.is_some() && column >= trailing_whitespace
+                    {
+                        Some(opts.theme.trailing_whitespace)
+                    } else if opts.color_column == Some(ruler + 1) {
+                        Some(opts.theme.color_column)
+                    } else {
+                        line_color
+                    };
+                    let cell_style = CellStyle {
+                        fg: colors.get(column as usize).cloned().flatten(),
+                        bg,
+                    };
+                    if cell_style != style {
+                        cell_style.push_to(&mut screen);
+                        style = cell_style;
+                    }
+
+                    if let Some(c) = c {
+                        screen.push(c);
+                    } else if self.break_on_line_end && x > 0 {
+                        break;
+                    } else {
+                        screen.push(' ');
+                    }
+                }
+
+                if style != CellStyle::default() {
+                    screen.push_str(&ResetColor.to_string());
+                }
+                screen.push('\n')
             }
-            screen.push('\n')
+            y += 1;
         }
 
         screen
@@ -116,7 +611,376 @@ impl Renderer for StringRenderer {
 
 #[cfg(test)]
 mod tests {
-    //    use super::*;
+    use super::*;
+
+    #[test]
+    fn test_string_renderer_wide_chars() {
+        let mut editor = Editor::from("a日本b");
+        let mut opts = RenderOpts {
+            view: Rect {
+                location: Vector2(0, 0),
+                width: 6,
+                height: 1,
+            },
+            ..RenderOpts::default()
+        };
+        assert_eq!(StringRenderer::new().render(&editor, opts), "a日本b\n");
+
+        // characters cut by the edges of the view are replaced with spaces
+        opts.view = Rect {
+            location: Vector2(2, 0),
+            width: 4,
+            height: 1,
+        };
+        assert_eq!(StringRenderer::new().render(&editor, opts), " 本b\n");
+        opts.view = Rect {
+            location: Vector2(0, 0),
+            width: 2,
+            height: 1,
+        };
+        assert_eq!(StringRenderer::new().render(&editor, opts), "a \n");
+
+        editor.set_cursor((2, 0));
+        assert_eq!(opts.screen_column(&editor, editor.cursor_pos()), 3);
+        assert_eq!(opts.buffer_column(&editor, 0, 4), 2);
+        assert_eq!(opts.buffer_column(&editor, 0, 5), 3);
+    }
+
+    #[test]
+    fn test_string_renderer_wrap() {
+        let editor = Editor::from("one two three\nabcdefgh\nx");
+        let opts = RenderOpts {
+            view: Rect {
+                location: Vector2(0, 0),
+                width: 8,
+                height: 6,
+            },
+            line_numbers: LineNumberMode::Absolute,
+            wrap: true,
+            ..RenderOpts::default()
+        };
+        assert_eq!(opts.wrap_points(&editor, 0, 6), vec![0, 4, 8]);
+        assert_eq!(opts.wrap_points(&editor, 1, 6), vec![0, 6]);
+
+        let text = StringRenderer::new().render(&editor, opts);
+        assert_eq!(
+            text,
+            "1 one   \n  two   \n  three \n2 abcdef\n  gh    \n3 x     \n"
+        );
+
+        assert_eq!(
+            opts.wrapped_position(&editor, Vector2(5, 0)),
+            Some(Vector2(1, 1))
+        );
+        assert_eq!(
+            opts.wrapped_position(&editor, Vector2(8, 1)),
+            Some(Vector2(2, 4))
+        );
+        assert_eq!(opts.wrapped_position(&editor, Vector2(0, 3)), None);
+        assert_eq!(opts.wrapped_location(&editor, Vector2(1, 1)), Vector2(5, 0));
+        assert_eq!(opts.wrapped_location(&editor, Vector2(5, 1)), Vector2(7, 0));
+        assert_eq!(opts.wrapped_location(&editor, Vector2(0, 5)), Vector2(0, 2));
+    }
+
+    #[test]
+    fn test_changed_lines() {
+        let previous = vec!["a".to_string(), "b".to_string()];
+        assert_eq!(
+            changed_lines(&previous, "a\nc\nd\n"),
+            vec![(1, "c"), (2, "d")]
+        );
+        assert_eq!(changed_lines(&previous, "a\nb\n"), vec![]);
+        assert_eq!(changed_lines(&[], "a\n"), vec![(0, "a")]);
+    }
+
+    #[test]
+    fn test_string_renderer_line_numbers() {
+        let editor = Editor::from(
+            (1..=10)
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+        let opts = RenderOpts {
+            view: Rect {
+                location: Vector2(0, 8),
+                width: 6,
+                height: 3,
+            },
+            line_numbers: LineNumberMode::Absolute,
+            ..RenderOpts::default()
+        };
+
+        // rows past the end of the buffer have no line number and are marked with a ~
+        let text = StringRenderer::new().render(&editor, opts);
+        assert_eq!(
+            text,
+            format!(
+                " 9 9  \n10 10 \n{}~{}     \n",
+                SetForegroundColor(Theme::default().end_of_buffer),
+                ResetColor
+            )
+        );
+    }
+
+    #[test]
+    fn test_string_renderer_highlighter() {
+        struct FirstWord;
+        impl Highlighter for FirstWord {
+            fn style(&self, line: &str, _: i32) -> Vec<(std::ops::Range<usize>, Color)> {
+                vec![(0..line.find(' ').unwrap_or(line.len()), Color::Red)]
+            }
+        }
+
+        let editor = Editor::from("ab c\nd");
+        let opts = RenderOpts {
+            view: Rect {
+                location: Vector2(0, 0),
+                width: 4,
+                height: 2,
+            },
+            ..RenderOpts::default()
+        };
+
+        let renderer = StringRenderer {
+            highlighter: Some(Rc::new(FirstWord)),
+            ..StringRenderer::new()
+        };
+
+        let red = format!("{}{}", ResetColor, SetForegroundColor(Color::Red));
+        assert_eq!(
+            renderer.render(&editor, opts),
+            format!("{}ab{} c\n{}d{}   \n", red, ResetColor, red, ResetColor)
+        );
+    }
+
+    #[test]
+    fn test_string_renderer_current_line() {
+        let mut editor = Editor::from("a\nb");
+        editor.set_cursor((0, 1));
+
+        let opts = RenderOpts {
+            view: Rect {
+                location: Vector2(0, 0),
+                width: 2,
+                height: 2,
+            },
+            highlight_current_line: true,
+            theme: Theme {
+                current_line: Color::Blue,
+                ..Theme::default()
+            },
+            ..RenderOpts::default()
+        };
+
+        // the highlight stops at the edge of the view
+        let text = StringRenderer::new().render(&editor, opts);
+        assert_eq!(
+            text,
+            format!(
+                "a \n{}{}b {}\n",
+                ResetColor,
+                SetBackgroundColor(Color::Blue),
+                ResetColor
+            )
+        );
+    }
+
+    #[test]
+    fn test_string_renderer_color_column() {
+        let editor = Editor::from("abcd\nx");
+        let opts = RenderOpts {
+            view: Rect {
+                location: Vector2(1, 0),
+                width: 2,
+                height: 2,
+            },
+            color_column: Some(3),
+            theme: Theme {
+                color_column: Color::Blue,
+                ..Theme::default()
+            },
+            ..RenderOpts::default()
+        };
+
+        // the ruler stays on the third column of the line when the view is scrolled
+        let text = StringRenderer::new().render(&editor, opts);
+        let ruler = format!("{}{}", ResetColor, SetBackgroundColor(Color::Blue));
+        assert_eq!(
+            text,
+            format!("b{}c{}\n {} {}\n", ruler, ResetColor, ruler, ResetColor)
+        );
+    }
+
+    #[test]
+    fn test_string_renderer_selection() {
+        let mut editor = Editor::from("ab\ncd");
+        editor.set_cursor((1, 1));
+        editor.begin_line_select();
+
+        let opts = RenderOpts {
+            view: Rect {
+                location: Vector2(0, 0),
+                width: 3,
+                height: 2,
+            },
+            theme: Theme {
+                selection: Color::Blue,
+                ..Theme::default()
+            },
+            ..RenderOpts::default()
+        };
+
+        // line selections span the width of the view
+        let text = StringRenderer::new().render(&editor, opts);
+        assert_eq!(
+            text,
+            format!(
+                "ab \n{}{}cd {}\n",
+                ResetColor,
+                SetBackgroundColor(Color::Blue),
+                ResetColor
+            )
+        );
+    }
+
+    #[test]
+    fn test_string_renderer_search_matches() {
+        let editor = Editor::from("abc");
+        let opts = RenderOpts {
+            view: Rect {
+                location: Vector2(0, 0),
+                width: 3,
+                height: 1,
+            },
+            theme: Theme {
+                search_match: Color::Yellow,
+                ..Theme::default()
+            },
+            ..RenderOpts::default()
+        };
+
+        let text = StringRenderer {
+            search_matches: vec![(Vector2(1, 0), Vector2(2, 0))],
+            ..StringRenderer::new()
+        }
+        .render(&editor, opts);
+        assert_eq!(
+            text,
+            format!(
+                "a{}{}b{}c\n",
+                ResetColor,
+                SetBackgroundColor(Color::Yellow),
+                ResetColor
+            )
+        );
+    }
+
+    #[test]
+    fn test_string_renderer_whitespace() {
+        let editor = Editor::from("a b\t ");
+
+        let opts = RenderOpts {
+            view: Rect {
+                location: Vector2(0, 0),
+                width: 6,
+                height: 1,
+            },
+            render_whitespace: true,
+            theme: Theme {
+                trailing_whitespace: Color::Red,
+                ..Theme::default()
+            },
+            tab_width: 1,
+            ..RenderOpts::default()
+        };
+
+        let text = StringRenderer::new().render(&editor, opts);
+        assert_eq!(
+            text,
+            format!(
+                "a·b{}{}→·{} \n",
+                ResetColor,
+                SetBackgroundColor(Color::Red),
+                ResetColor
+            )
+        );
+    }
+
+    #[test]
+    fn test_text_box() {
+        let view = Rect {
+            location: Vector2(0, 0),
+            width: 12,
+            height: 5,
+        };
+        let (area, rows) = text_box("one\ntwo\nthree\nfour", 1, view);
+        assert_eq!(
+            (area.location, area.width, area.height),
+            (Vector2(1, 0), 9, 5)
+        );
+        assert_eq!(
+            rows,
+            vec![
+                "┌───────┐",
+                "│ two   │",
+                "│ three │",
+                "│ four  │",
+                "└───────┘"
+            ]
+        );
+
+        let (_, rows) = text_box("a long line", 0, view);
+        assert_eq!(rows, vec!["┌──────────┐", "│ a long l │", "└──────────┘"]);
+    }
+
+    #[test]
+    fn test_string_renderer_tabs() {
+        let editor = Editor::from("\ta\tb");
+
+        let opts = RenderOpts {
+            view: Rect {
+                location: Vector2(1, 0),
+                width: 6,
+                height: 1,
+            },
+            tab_width: 3,
+            ..RenderOpts::default()
+        };
+
+        let text = StringRenderer::new().render(&editor, opts);
+        assert_eq!(text, "  a   \n");
+
+        assert_eq!(opts.screen_column(&editor, Vector2(2, 0)), 4);
+        assert_eq!(opts.screen_column(&editor, Vector2(6, 0)), 10);
+        assert_eq!(opts.buffer_column(&editor, 0, 2), 0);
+        assert_eq!(opts.buffer_column(&editor, 0, 3), 1);
+        assert_eq!(opts.buffer_column(&editor, 0, 9), 5);
+    }
+
+    #[test]
+    fn test_string_renderer_relative_line_numbers() {
+        let mut editor = Editor::from("a\nb\nc");
+        editor.set_cursor((0, 1));
+
+        let mut opts = RenderOpts {
+            view: Rect {
+                location: Vector2(0, 0),
+                width: 3,
+                height: 3,
+            },
+            line_numbers: LineNumberMode::Relative,
+            ..RenderOpts::default()
+        };
+
+        let text = StringRenderer::new().render(&editor, opts);
+        assert_eq!(text, "1 a\n0 b\n1 c\n");
+
+        opts.line_numbers = LineNumberMode::Hybrid;
+        let text = StringRenderer::new().render(&editor, opts);
+        assert_eq!(text, "1 a\n2 b\n1 c\n");
+    }
+
     //    const SAMPLE_TEXT: &'static str = include_str!("../resources/sample_text.txt");
 
     //    #[test]